@@ -1,44 +1,235 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, ValueEnum};
-use nc_exec::{ExecConfig, SandboxBackend};
-use nc_exec_wasmtime::WasmtimeBackend;
-use std::path::PathBuf;
+use nc_exec::{
+    read_payloads, AsyncSandboxBackend, BatchRecord, BatchWriter, CancelToken, Capabilities,
+    CompilerProfile, CompilerStrategy, DeterminismProfile, ExecConfig, ExecProof, KvLimits, Mount,
+    OutputCapture, ResourceLimits, SandboxBackend, TenantManifest,
+};
+use nc_exec_wasmtime::{EngineOptions, WasmtimeBackend};
+use nc_state::TenantState;
+use std::{fs, path::{Path, PathBuf}, sync::Arc, time::Instant};
 
 #[derive(ValueEnum, Clone)]
 enum Backend {
     Wasmtime,
 }
 
+#[derive(ValueEnum, Clone, Copy)]
+enum Compiler {
+    Cranelift,
+    Winch,
+}
+
 #[derive(Parser)]
 #[command(name="nc-exec", about="Night Core™ — pluggable executor")]
 struct Args {
     #[arg(long)] tenant: String,
     #[arg(long)] module: PathBuf,
+    /// Tenant manifest (defaults to manifest.json next to the module, if present).
+    /// Command-line flags add to what it declares.
+    #[arg(long)] manifest: Option<PathBuf>,
+    /// Writable state directory, mounted at /state in the guest
     #[arg(long)] state_dir: Option<PathBuf>,
+    /// Extra mount HOST:GUEST[:ro|rw] (repeatable, read-only by default)
+    #[arg(long = "mount", value_parser = parse_mount)] mounts: Vec<Mount>,
     #[arg(long, value_enum, default_value_t=Backend::Wasmtime)] backend: Backend,
+    /// Cache precompiled artifacts of verified modules in this directory
+    #[arg(long)] cache_dir: Option<PathBuf>,
+    /// Execute through the async backend on a tokio runtime
+    #[arg(long = "async")] run_async: bool,
+    /// Guest environment variable (repeatable), e.g. --env GREETING=hi
+    #[arg(long = "env", value_parser = parse_env)] env: Vec<(String, String)>,
+    /// Expose the host environment to the guest
+    #[arg(long)] inherit_env: bool,
+    /// Granted permission (repeatable): stdout, stderr, env, clock, random, net,
+    /// log, kv, fs:read:<path>, fs:write:<path>. Mounts grant their own fs access, and --env grants env.
+    /// kv state lives in ./state/<tenant>, as with `nightcore run`.
+    /// Without a manifest, defaults to stdout + stderr.
+    #[arg(long = "allow")]
+    allow: Vec<String>,
+    /// Feed this file to the guest's stdin as a single request
+    #[arg(long, conflicts_with = "batch")] input: Option<PathBuf>,
+    /// Run once per JSONL record, feeding each line to the guest's stdin
+    #[arg(long)] batch: Option<PathBuf>,
+    /// Where --input / --batch results go (JSONL, one line per record)
+    #[arg(long, default_value = "results.jsonl")] results: PathBuf,
+    /// Deterministic run: virtual clocks, seeded randomness, no threads,
+    /// no network, no host environment
+    #[arg(long)] deterministic: bool,
+    /// Seed for --deterministic (overrides the manifest's)
+    #[arg(long, requires = "deterministic")] seed: Option<u64>,
+    /// Code generator (overrides the manifest's compiler.strategy)
+    #[arg(long, value_enum)] compiler: Option<Compiler>,
+    /// Call this manifest-declared export instead of the default entrypoint
+    #[arg(long = "fn")] export: Option<String>,
+    /// Guest program arguments (after `--`); with --fn, the export's arguments
+    #[arg(last = true)] args: Vec<String>,
+}
+
+fn parse_mount(s: &str) -> Result<Mount, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    match parts.as_slice() {
+        [host, guest] => Ok(Mount::read_only(*host, *guest)),
+        [host, guest, "ro"] => Ok(Mount::read_only(*host, *guest)),
+        [host, guest, "rw"] => Ok(Mount::read_write(*host, *guest)),
+        _ => Err(format!("expected HOST:GUEST[:ro|rw], got '{}'", s)),
+    }
+}
+
+fn parse_env(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((k, v)) if !k.is_empty() => Ok((k.to_string(), v.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", s)),
+    }
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
 
-    let preopen = args.state_dir.as_ref().map(|p| vec![p.clone()]).unwrap_or_default();
-
-    let cfg = ExecConfig {
-        tenant: args.tenant,
-        module_path: args.module,
-        preopen_dirs: preopen,
-        env: vec![],
-        fuel: Some(5_000_000),
-        time_limit_ms: Some(5_000),
+    let manifest_path = args.manifest.clone().or_else(|| {
+        args.module.parent()
+            .map(|dir| dir.join("manifest.json"))
+            .filter(|path| path.exists())
+    });
+    let manifest = match &manifest_path {
+        Some(path) => {
+            let raw = fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            Some(TenantManifest::parse(&raw)
+                .with_context(|| format!("invalid manifest {}", path.display()))?)
+        }
+        None => None,
     };
+    let mut cfg = match &manifest {
+        Some(manifest) => manifest.exec_config(&args.tenant, &args.module)?,
+        None => ExecConfig {
+            tenant: args.tenant.clone(),
+            module_path: args.module.clone(),
+            entrypoint: "_start".into(),
+            call_args: vec![],
+            stdin: None,
+            mounts: vec![],
+            env: vec![],
+            args: vec![],
+            inherit_env: false,
+            fuel: None,
+            time_limit_ms: None,
+            limits: ResourceLimits::default(),
+            output: OutputCapture::default(),
+            capabilities: Capabilities::parse(&["stdout", "stderr"])?,
+            kv: None,
+            kv_limits: KvLimits::default(),
+            deterministic: None,
+            compiler: CompilerProfile::default(),
+            cancel: CancelToken::default(),
+            verified_sha256: None,
+        },
+    };
+
+    cfg.capabilities.merge(Capabilities::parse(&args.allow)?);
+
+    let mut mounts = args.mounts;
+    if let Some(dir) = &args.state_dir {
+        mounts.push(Mount::read_write(dir, "/state"));
+    }
+    // Mounts given on the command line are granted along with the rest.
+    let mut implied = Capabilities::default();
+    for m in &mounts {
+        let list = if m.read_only { &mut implied.fs_read } else { &mut implied.fs_write };
+        list.push(m.guest_path.trim_end_matches('/').to_string());
+    }
+    // Likewise `--env` grants `env`; the variables are never dropped silently.
+    implied.env = !args.env.is_empty();
+    cfg.capabilities.merge(implied);
+    cfg.mounts.extend(mounts);
+    cfg.env.extend(args.env);
+    match &args.export {
+        // Trailing arguments are typed call arguments for the export...
+        Some(export) => {
+            let manifest = manifest.as_ref()
+                .ok_or_else(|| anyhow!("--fn needs a manifest declaring '{}'", export))?;
+            cfg.call_args = manifest.invocation_args(export, &args.args)?;
+            cfg.entrypoint = export.clone();
+        }
+        // ...otherwise guest argv.
+        None => cfg.args.extend(args.args),
+    }
+    if args.deterministic {
+        let profile = cfg.deterministic.get_or_insert_with(DeterminismProfile::default);
+        if let Some(seed) = args.seed {
+            profile.seed = seed;
+        }
+    }
+    if let Some(compiler) = args.compiler {
+        cfg.compiler.strategy = Some(match compiler {
+            Compiler::Cranelift => CompilerStrategy::Cranelift,
+            Compiler::Winch => CompilerStrategy::Winch,
+        });
+    }
+    if cfg.capabilities.kv {
+        cfg.kv = Some(Arc::new(TenantState::open(".", &args.tenant)?));
+    }
+    cfg.inherit_env |= args.inherit_env;
+    cfg.fuel = cfg.fuel.or(Some(5_000_000));
+    cfg.time_limit_ms = cfg.time_limit_ms.or(Some(5_000));
+    cfg.limits.max_memory_mb = cfg.limits.max_memory_mb.or(Some(256));
+    cfg.output.echo = true;
 
     match args.backend {
         Backend::Wasmtime => {
-            let be = WasmtimeBackend;
-            be.verify(&cfg.module_path)?;
-            let proof = be.execute(&cfg)?;
-            println!("{}", serde_json::to_string_pretty(&proof)?);
+            let be = WasmtimeBackend::new(&EngineOptions::default())?;
+            let be = match args.cache_dir {
+                Some(dir) => be.with_cache_dir(dir),
+                None => be,
+            };
+            cfg.verified_sha256 = Some(be.verify(&cfg.module_path, &cfg.capabilities)?);
+            let run = |cfg: &ExecConfig| -> Result<ExecProof> {
+                if args.run_async {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(be.execute_async(cfg))
+                } else {
+                    be.execute(cfg)
+                }
+            };
+
+            let payloads = match (&args.input, &args.batch) {
+                (Some(path), _) => Some(read_payloads(path, false)?),
+                (_, Some(path)) => Some(read_payloads(path, true)?),
+                _ => None,
+            };
+            match payloads {
+                Some(payloads) => run_batch(&mut cfg, &payloads, &args.results, run)?,
+                None => println!("{}", serde_json::to_string_pretty(&run(&cfg)?)?),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Request/response mode: one run per payload, one result line per run.
+fn run_batch(
+    cfg: &mut ExecConfig,
+    payloads: &[Vec<u8>],
+    results: &Path,
+    run: impl Fn(&ExecConfig) -> Result<ExecProof>,
+) -> Result<()> {
+    // stdout is the result; echoing it per record would just be noise.
+    cfg.output.echo = false;
+    let mut out = BatchWriter::create(results)?;
+    let mut ok = 0;
+    for (index, payload) in payloads.iter().enumerate() {
+        cfg.stdin = Some(payload.clone());
+        let t0 = Instant::now();
+        let res = run(cfg);
+        let record = BatchRecord::new(index, payload, &res, t0.elapsed().as_millis() as u64);
+        if record.status == "ok" {
+            ok += 1;
         }
+        println!("  • Record {}: {} ({} ms)", index, record.status, record.duration_ms);
+        out.write(&record)?;
     }
+    println!("📦 {}/{} records ok → {}", ok, payloads.len(), results.display());
     Ok(())
 }
@@ -1,8 +1,9 @@
 use anyhow::{anyhow, Context, Result};
-use nc_exec::{ExecConfig, ExecProof, SandboxBackend};
-use std::{fs, path::Path, process::Stdio};
-use tempfile::tempdir;
+use async_trait::async_trait;
+use nc_exec::{AsyncSandboxBackend, CancelToken, Capabilities, ExecConfig, ExecOutcome, ExecProof, SandboxBackend};
+use std::{path::Path, process::Stdio, time::Duration};
 use tokio::process::Command;
+use tempfile::tempdir;
 
 /// Firecracker adapter (MVP):
 /// - Writes minimal VM config
@@ -13,13 +14,33 @@ pub struct FirecrackerBackend;
 impl SandboxBackend for FirecrackerBackend {
     fn name(&self) -> &'static str { "firecracker" }
 
-    fn verify(&self, module_path: &Path) -> Result<()> {
+    fn verify(&self, module_path: &Path, capabilities: &Capabilities) -> Result<String> {
         // Reuse host-side verify (same checks).
         // You could enforce "require-signed-guest-rootfs" here later.
-        nc_exec::default_verify(module_path)
+        nc_exec::default_verify(module_path, capabilities)
     }
 
     fn execute(&self, cfg: &ExecConfig) -> Result<ExecProof> {
+        // Process and API waits live on the async path; drive it to completion
+        // on a private runtime. Blocking on one from inside another runtime
+        // panics, so a caller already on a runtime gets its own thread.
+        let run = || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(self.execute_async(cfg))
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(_) => std::thread::scope(|s| s.spawn(run).join())
+                .map_err(|_| anyhow!("firecracker run panicked"))?,
+            Err(_) => run(),
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncSandboxBackend for FirecrackerBackend {
+    async fn execute_async(&self, cfg: &ExecConfig) -> Result<ExecProof> {
         // Preconditions
         let fc = which::which("firecracker")
             .map_err(|_| anyhow!("firecracker binary not found in PATH"))?;
@@ -37,7 +58,7 @@ impl SandboxBackend for FirecrackerBackend {
         let vcpus = std::env::var("NC_FC_VCPUS").ok().and_then(|s| s.parse().ok()).unwrap_or(1);
         let mem_mib = std::env::var("NC_FC_MEM").ok().and_then(|s| s.parse().ok()).unwrap_or(512);
 
-        let cfg = serde_json::json!({
+        let vm_cfg = serde_json::json!({
           "boot-source": { "kernel_image_path": kernel, "boot_args": "console=ttyS0 reboot=k panic=1 pci=off" },
           "drives": [{
             "drive_id": "rootfs",
@@ -47,7 +68,7 @@ impl SandboxBackend for FirecrackerBackend {
           }],
           "machine-config": { "vcpu_count": vcpus, "mem_size_mib": mem_mib, "ht_enabled": false }
         });
-        fs::write(&cfg_path, serde_json::to_vec_pretty(&cfg)?)?;
+        tokio::fs::write(&cfg_path, serde_json::to_vec_pretty(&vm_cfg)?).await?;
 
         // Launch Firecracker with API socket
         let mut child = Command::new(fc)
@@ -55,71 +76,81 @@ impl SandboxBackend for FirecrackerBackend {
             .stdin(Stdio::null())
             .stdout(Stdio::inherit())
             .stderr(Stdio::inherit())
+            .kill_on_drop(true)
             .spawn()
             .context("spawn firecracker")?;
 
-        // POST config via `curl` (MVP: rely on system curl)
-        // You can replace with direct Unix-domain-socket HTTP in Rust later.
-        let apply_cfg = || -> Result<()> {
-            let body = fs::read(&cfg_path)?;
-            let status = Command::new("curl")
-                .args([
-                    "-sS", "-X", "PUT",
-                    "--unix-socket", api_sock.to_str().unwrap(),
-                    "http://localhost/boot-source",
-                    "-d", &serde_json::to_string(&cfg["boot-source"])?,
-                ])
-                .status()?;
-            if !status.success() { return Err(anyhow!("apply boot-source failed")); }
-
-            let status = Command::new("curl")
-                .args([
-                    "-sS", "-X", "PUT",
-                    "--unix-socket", api_sock.to_str().unwrap(),
-                    "http://localhost/drives/rootfs",
-                    "-d", &serde_json::to_string(&cfg["drives"][0])?,
-                ])
-                .status()?;
-            if !status.success() { return Err(anyhow!("apply rootfs failed")); }
-
-            let status = Command::new("curl")
-                .args([
-                    "-sS", "-X", "PUT",
-                    "--unix-socket", api_sock.to_str().unwrap(),
-                    "http://localhost/machine-config",
-                    "-d", &serde_json::to_string(&cfg["machine-config"])?,
-                ])
-                .status()?;
-            if !status.success() { return Err(anyhow!("apply machine-config failed")); }
-
-            let status = Command::new("curl")
-                .args([
-                    "-sS", "-X", "PUT",
-                    "--unix-socket", api_sock.to_str().unwrap(),
-                    "http://localhost/actions",
-                    "-d", r#"{"action_type":"InstanceStart"}"#,
-                ])
-                .status()?;
-            if !status.success() { return Err(anyhow!("start vm failed")); }
-            Ok(())
+        // Configure and boot through the API socket; a cancelled run stops
+        // waiting on the VM and reports it as interrupted. Nothing inside the
+        // guest can run the module yet, so even a clean boot is a host error.
+        let boot = async {
+            api_put(&api_sock, "boot-source", &serde_json::to_string(&vm_cfg["boot-source"])?).await?;
+            api_put(&api_sock, "drives/rootfs", &serde_json::to_string(&vm_cfg["drives"][0])?).await?;
+            api_put(&api_sock, "machine-config", &serde_json::to_string(&vm_cfg["machine-config"])?).await?;
+            api_put(&api_sock, "actions", r#"{"action_type":"InstanceStart"}"#).await
+        };
+        let outcome = tokio::select! {
+            res = boot => {
+                res?;
+                ExecOutcome::HostError {
+                    message: "guest agent not available: the microVM booted but cannot run tenant modules yet".into(),
+                }
+            }
+            _ = cancelled(&cfg.cancel) => ExecOutcome::Trapped { trap: "interrupt".into() },
         };
-        apply_cfg()?;
 
         // TODO: exec ‘nc-exec-cli’ inside guest (via init or vsock agent).
-        // For MVP, we’ll return a stub proof to validate end-to-end wiring.
         // Next patch: add a guest agent that receives cfg over vsock and runs wasmtime.
         let proof = ExecProof {
             tenant: cfg.tenant.clone(),
-            module_sha256: "<vm-run-stub>".into(),
+            module_sha256: cfg.verified_sha256.clone().unwrap_or_default(),
             signer_key_b64: "<verified>".into(),
             started_at: chrono::Utc::now().to_rfc3339(),
             finished_at: chrono::Utc::now().to_rfc3339(),
-            status: "ok".into(),
+            status: outcome.status().into(),
+            outcome,
             backend: self.name().into(),
+            abi: None,
+            fuel_consumed: None,
+            peak_memory_bytes: None,
+            stdout_sha256: None,
+            stderr_sha256: None,
+            output_truncated: false,
+            invocation: None,
+            determinism: None,
+            host_api: None,
+            stdin_sha256: None,
+            compiler: None,
+            engine_fingerprint: None,
+            stdout: vec![],
         };
 
         // Ensure Firecracker exits (MVP expects kernel to exit quickly)
-        let _ = child.kill();
+        let _ = child.kill().await;
         Ok(proof)
     }
 }
+
+/// Resolves once `cancel` is set.
+async fn cancelled(cancel: &CancelToken) {
+    while !cancel.is_cancelled() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// PUT one Firecracker API resource over the VM's unix socket.
+/// (MVP: rely on system curl; replace with direct Unix-domain-socket HTTP later.)
+async fn api_put(api_sock: &Path, resource: &str, body: &str) -> Result<()> {
+    let status = Command::new("curl")
+        .args(["-sS", "-X", "PUT", "--unix-socket"])
+        .arg(api_sock)
+        .arg(format!("http://localhost/{}", resource))
+        .args(["-d", body])
+        .status()
+        .await
+        .with_context(|| format!("PUT /{}", resource))?;
+    if !status.success() {
+        return Err(anyhow!("apply {} failed", resource));
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use std::{
+    collections::HashSet,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use wasmtime::{component::Component, Engine, Module};
+
+/// Content-addressed store of precompiled artifacts:
+/// `<dir>/<module sha256>-<engine fingerprint>.cwasm`.
+///
+/// Only modules whose exact bytes passed `SandboxBackend::verify` in this
+/// process are loaded from or written to the cache; anything else is compiled
+/// from source. Artifacts are native code, so `dir` must be as trusted as the
+/// nightcore binary itself.
+pub struct ModuleCache {
+    dir: PathBuf,
+    verified: Mutex<HashSet<String>>,
+}
+
+/// Something wasmtime can compile, serialize and map back in.
+pub(crate) trait Artifact: Sized {
+    fn compile(engine: &Engine, bytes: &[u8]) -> Result<Self>;
+    fn serialize(&self) -> Result<Vec<u8>>;
+    /// # Safety
+    /// `path` must hold an artifact this process (or a trusted peer) serialized.
+    unsafe fn deserialize_file(engine: &Engine, path: &Path) -> Result<Self>;
+}
+
+impl Artifact for Module {
+    fn compile(engine: &Engine, bytes: &[u8]) -> Result<Self> {
+        Module::new(engine, bytes)
+    }
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Module::serialize(self)
+    }
+    unsafe fn deserialize_file(engine: &Engine, path: &Path) -> Result<Self> {
+        Module::deserialize_file(engine, path)
+    }
+}
+
+impl Artifact for Component {
+    fn compile(engine: &Engine, bytes: &[u8]) -> Result<Self> {
+        Component::new(engine, bytes)
+    }
+    fn serialize(&self) -> Result<Vec<u8>> {
+        Component::serialize(self)
+    }
+    unsafe fn deserialize_file(engine: &Engine, path: &Path) -> Result<Self> {
+        Component::deserialize_file(engine, path)
+    }
+}
+
+impl ModuleCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), verified: Mutex::new(HashSet::new()) }
+    }
+
+    /// Record that the module with this (lowercase hex) SHA-256 passed its signature check.
+    pub fn mark_verified(&self, sha256: &str) {
+        self.verified.lock().unwrap().insert(sha256.to_string());
+    }
+
+    /// Load the artifact for `bytes` (whose digest is `sha256`) compiled by
+    /// `engine` (whose fingerprint is `fingerprint`), compiling and storing it
+    /// on a miss. Unverified modules bypass the cache entirely.
+    pub(crate) fn load<A: Artifact>(&self, engine: &Engine, fingerprint: &str, sha256: &str, bytes: &[u8]) -> Result<A> {
+        if !self.verified.lock().unwrap().contains(sha256) {
+            return A::compile(engine, bytes);
+        }
+        let path = self.dir.join(format!("{}-{}.cwasm", sha256, fingerprint));
+
+        if path.exists() {
+            // SAFETY: entries are only written below, from verified modules.
+            match unsafe { A::deserialize_file(engine, &path) } {
+                Ok(artifact) => {
+                    println!("⚡ Precompiled artifact loaded: {}", path.display());
+                    return Ok(artifact);
+                }
+                Err(e) => eprintln!("⚠️  Discarding stale artifact {}: {:#}", path.display(), e),
+            }
+        }
+
+        let artifact = A::compile(engine, bytes)?;
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("creating cache dir {}", self.dir.display()))?;
+        // Write-then-rename so concurrent runs (threads or processes) never
+        // map a half-written file; each writer gets its own temp file.
+        let mut tmp = tempfile::NamedTempFile::new_in(&self.dir)
+            .with_context(|| format!("creating temp file in {}", self.dir.display()))?;
+        tmp.write_all(&artifact.serialize()?)
+            .with_context(|| format!("writing {}", tmp.path().display()))?;
+        tmp.persist(&path).with_context(|| format!("writing {}", path.display()))?;
+        println!("🗃️  Precompiled artifact cached: {}", path.display());
+        Ok(artifact)
+    }
+}
@@ -0,0 +1,60 @@
+use nc_exec::DeterminismProfile;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, WasiCtxBuilder};
+
+/// Clock that advances a fixed step per read, independent of real time.
+struct VirtualClock {
+    start_ns: u64,
+    step_ns: u64,
+    reads: AtomicU64,
+}
+
+impl VirtualClock {
+    fn new(start_ns: u64, step_ns: u64) -> Self {
+        Self { start_ns, step_ns, reads: AtomicU64::new(0) }
+    }
+
+    fn tick(&self) -> u64 {
+        let n = self.reads.fetch_add(1, Ordering::Relaxed);
+        self.start_ns.saturating_add(n.saturating_mul(self.step_ns))
+    }
+}
+
+impl HostWallClock for VirtualClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(self.step_ns.max(1))
+    }
+
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.tick())
+    }
+}
+
+impl HostMonotonicClock for VirtualClock {
+    fn resolution(&self) -> u64 {
+        self.step_ns.max(1)
+    }
+
+    fn now(&self) -> u64 {
+        self.tick()
+    }
+}
+
+/// Swap host clocks and entropy for the profile's virtual ones. The secure
+/// and insecure sources get distinct streams derived from the same seed.
+/// ChaCha20 rather than `StdRng`, whose algorithm may change between `rand`
+/// releases and would break replay of recorded runs.
+pub(crate) fn apply(builder: &mut WasiCtxBuilder, profile: &DeterminismProfile) {
+    let start_ns = profile.clock_start_ms.saturating_mul(1_000_000);
+    builder
+        .wall_clock(VirtualClock::new(start_ns, profile.clock_step_ns))
+        .monotonic_clock(VirtualClock::new(0, profile.clock_step_ns))
+        .secure_random(ChaCha20Rng::seed_from_u64(profile.seed))
+        .insecure_random(ChaCha20Rng::seed_from_u64(profile.seed ^ 0x9e37_79b9_7f4a_7c15))
+        .insecure_random_seed(u128::from(profile.seed));
+}
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Result};
+use nc_exec::{CompilerProfile, CompilerStrategy};
+use sha2::{Digest, Sha256};
+use std::{
+    hash::{Hash, Hasher},
+    thread,
+    time::Duration,
+};
+use wasmtime::{Config, Engine, InstanceAllocationStrategy, PoolingAllocationConfig, Strategy};
+
+/// Wall-clock resolution of `time_limit_ms` and cancellation: the shared
+/// engine's epoch is bumped once per tick and each store checks its deadline
+/// and cancel token on every tick.
+pub const EPOCH_TICK_MS: u64 = 10;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Headroom between the guest stack budget and the fiber stack async guests
+/// run on (host calls need stack too).
+const ASYNC_STACK_HEADROOM: usize = 1024 * 1024;
+
+/// Tuning for the long-lived engine shared by every tenant run.
+#[derive(Debug, Clone)]
+pub struct EngineOptions {
+    /// Pre-reserve instance / memory / table slots instead of mmap-ing per run.
+    pub pooling: bool,
+    /// Slots in the pool, i.e. how many instances can be live at once.
+    pub pool_slots: u32,
+    /// Largest linear memory a pool slot can hold.
+    pub pool_max_memory_mb: u64,
+    /// Virtual address space reserved per linear memory; `None` keeps wasmtime's default.
+    pub memory_reservation_mb: Option<u64>,
+}
+
+impl Default for EngineOptions {
+    fn default() -> Self {
+        Self { pooling: false, pool_slots: 16, pool_max_memory_mb: 256, memory_reservation_mb: None }
+    }
+}
+
+/// Engine-level settings that vary per run. Each flavor gets its own
+/// long-lived engine (and its own artifact-cache fingerprint).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Flavor {
+    /// Serves `AsyncSandboxBackend::execute_async` only.
+    pub async_support: bool,
+    /// NaN canonicalisation on, threads / shared memory off.
+    pub deterministic: bool,
+    pub compiler: CompilerProfile,
+}
+
+/// A built engine and its fingerprint.
+#[derive(Clone)]
+pub(crate) struct SharedEngine {
+    pub engine: Engine,
+    pub fingerprint: String,
+}
+
+/// Build the shared engine. Fuel metering and epoch checks are always compiled
+/// in so one engine (and one artifact-cache key) serves metered and unmetered
+/// tenants alike; unmetered stores just get an unreachable budget.
+pub(crate) fn build_engine(opts: &EngineOptions, flavor: Flavor) -> Result<SharedEngine> {
+    let problems = flavor.compiler.problems(flavor.deterministic);
+    if !problems.is_empty() {
+        return Err(anyhow!("invalid compiler profile: {}", problems.join("; ")));
+    }
+    let mut config = Config::new();
+    config.async_support(flavor.async_support);
+    apply_profile(&mut config, &flavor.compiler, flavor.async_support);
+    if flavor.deterministic {
+        config.cranelift_nan_canonicalization(true);
+        // Relaxed SIMD results may otherwise depend on the host CPU.
+        config.relaxed_simd_deterministic(true);
+        config.wasm_threads(false);
+        config.wasm_shared_everything_threads(false);
+    }
+    config.consume_fuel(true);
+    config.epoch_interruption(true);
+    config.wasm_component_model(true);
+    if let Some(mb) = opts.memory_reservation_mb {
+        config.memory_reservation(mb * MIB);
+    }
+    if opts.pooling {
+        let mut pool = PoolingAllocationConfig::new();
+        pool.total_core_instances(opts.pool_slots)
+            .total_component_instances(opts.pool_slots)
+            .total_memories(opts.pool_slots)
+            .total_tables(opts.pool_slots)
+            .total_stacks(opts.pool_slots)
+            .max_memory_size((opts.pool_max_memory_mb * MIB) as usize);
+        config.allocation_strategy(InstanceAllocationStrategy::Pooling(pool));
+    }
+    let engine = Engine::new(&config)?;
+    spawn_epoch_ticker(&engine);
+    let fingerprint = engine_fingerprint(&engine, &flavor.compiler)?;
+    Ok(SharedEngine { engine, fingerprint })
+}
+
+fn apply_profile(config: &mut Config, profile: &CompilerProfile, async_support: bool) {
+    config.strategy(match profile.strategy.unwrap_or_default() {
+        CompilerStrategy::Cranelift => Strategy::Cranelift,
+        CompilerStrategy::Winch => Strategy::Winch,
+    });
+    if let Some(on) = profile.simd {
+        config.wasm_simd(on);
+    }
+    if let Some(on) = profile.relaxed_simd {
+        config.wasm_relaxed_simd(on);
+    }
+    if let Some(on) = profile.threads {
+        config.wasm_threads(on);
+    }
+    if let Some(on) = profile.multi_memory {
+        config.wasm_multi_memory(on);
+    }
+    if let Some(on) = profile.gc {
+        // GC types build on typed function references.
+        config.wasm_gc(on);
+        if on {
+            config.wasm_function_references(true);
+        }
+    }
+    if let Some(kb) = profile.max_wasm_stack_kb {
+        let stack = kb as usize * 1024;
+        config.max_wasm_stack(stack);
+        if async_support {
+            config.async_stack_size(stack + ASYNC_STACK_HEADROOM);
+        }
+    }
+}
+
+/// Hash of every engine setting that affects compiled code (wasmtime version,
+/// target, fuel / epoch instrumentation, proposals, code generator, …). Any
+/// change yields a new fingerprint, and so a new artifact-cache key. SHA-256
+/// rather than `DefaultHasher`, whose output may change between Rust releases
+/// and would then orphan (or worse, alias) cached artifacts.
+fn engine_fingerprint(engine: &Engine, profile: &CompilerProfile) -> Result<String> {
+    let mut hasher = Sha256Hasher(Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+    hasher.0.update(serde_json::to_vec(profile)?);
+    Ok(format!("{:x}", hasher.0.finalize()))
+}
+
+/// Feeds `Hash` input into SHA-256; the fingerprint is the full digest, not
+/// [`Hasher::finish`].
+struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        u64::from_le_bytes(digest[..8].try_into().unwrap())
+    }
+}
+
+/// Advance the engine epoch every tick until the engine is dropped.
+fn spawn_epoch_ticker(engine: &Engine) {
+    let weak = engine.weak();
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_millis(EPOCH_TICK_MS));
+        match weak.upgrade() {
+            Some(engine) => engine.increment_epoch(),
+            None => break,
+        }
+    });
+}
@@ -0,0 +1,222 @@
+//! `nightcore` host import module for core modules:
+//!
+//! ```text
+//! log(level, msg_ptr, msg_len) -> i32             level: 0 debug, 1 info, 2 warn, 3 error
+//! kv_get(key_ptr, key_len, buf_ptr, buf_cap) -> i32   value length; written only if it fits
+//! kv_put(key_ptr, key_len, val_ptr, val_len) -> i32
+//! kv_delete(key_ptr, key_len) -> i32                  1 deleted, 0 absent
+//! ```
+//!
+//! Negative results are the `ERR_*` codes below. Verification already rejects
+//! modules importing a function without its `log` / `kv` permission; the
+//! functions still re-check and return `ERR_DENIED`.
+
+use crate::HostState;
+use anyhow::Result;
+use chrono::Utc;
+use nc_exec::{ExecConfig, HostApiStats, KvLimits, KvStore};
+use serde::Serialize;
+use std::sync::Arc;
+use wasmtime::{Caller, Linker};
+
+pub(crate) const MODULE: &str = "nightcore";
+
+const OK: i32 = 0;
+const ERR_NOT_FOUND: i32 = -1;
+const ERR_DENIED: i32 = -2;
+const ERR_TOO_LARGE: i32 = -3;
+const ERR_QUOTA: i32 = -4;
+const ERR_INVALID: i32 = -5;
+const ERR_IO: i32 = -6;
+
+/// Log entries kept per run; later calls are counted as dropped.
+const MAX_LOG_ENTRIES: usize = 1000;
+const MAX_LOG_MESSAGE_BYTES: usize = 4096;
+
+/// One guest log call, persisted to `logs/<tenant>/guest_log.jsonl`.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct LogEntry {
+    pub timestamp: String,
+    pub level: &'static str,
+    pub message: String,
+}
+
+/// Per-store state behind the `nightcore` imports.
+pub(crate) struct HostApi {
+    tenant: String,
+    echo: bool,
+    log: bool,
+    kv: Option<Arc<dyn KvStore>>,
+    limits: KvLimits,
+    pub entries: Vec<LogEntry>,
+    pub stats: HostApiStats,
+}
+
+impl HostApi {
+    pub fn new(cfg: &ExecConfig) -> Self {
+        Self {
+            tenant: cfg.tenant.clone(),
+            echo: cfg.output.echo,
+            log: cfg.capabilities.log,
+            kv: cfg.kv.clone().filter(|_| cfg.capabilities.kv),
+            limits: cfg.kv_limits.clone(),
+            entries: vec![],
+            stats: HostApiStats::default(),
+        }
+    }
+
+    fn reject(&mut self, code: i32) -> i32 {
+        self.stats.rejected += 1;
+        code
+    }
+
+    fn log(&mut self, level: i32, message: &[u8]) -> i32 {
+        if !self.log {
+            return self.reject(ERR_DENIED);
+        }
+        let level = match level {
+            0 => "debug",
+            1 => "info",
+            2 => "warn",
+            3 => "error",
+            _ => return self.reject(ERR_INVALID),
+        };
+        if self.entries.len() >= MAX_LOG_ENTRIES {
+            self.stats.log_dropped += 1;
+            return OK;
+        }
+        let message = &message[..message.len().min(MAX_LOG_MESSAGE_BYTES)];
+        let message = String::from_utf8_lossy(message).into_owned();
+        if self.echo {
+            // Same treatment as captured stdout: no escape sequences reach the terminal.
+            println!("📝 {} [{}] {}", self.tenant, level, crate::output::sanitize(message.as_bytes()));
+        }
+        self.entries.push(LogEntry { timestamp: Utc::now().to_rfc3339(), level, message });
+        self.stats.log_entries += 1;
+        OK
+    }
+
+    /// The store, once the permission and key checks pass.
+    fn kv_for(&mut self, key: &[u8]) -> Result<Arc<dyn KvStore>, i32> {
+        let Some(kv) = self.kv.clone() else { return Err(self.reject(ERR_DENIED)) };
+        if key.is_empty() {
+            return Err(self.reject(ERR_INVALID));
+        }
+        if key.len() > self.limits.max_key_bytes {
+            return Err(self.reject(ERR_TOO_LARGE));
+        }
+        Ok(kv)
+    }
+
+    fn kv_get(&mut self, key: &[u8]) -> Result<Vec<u8>, i32> {
+        let kv = self.kv_for(key)?;
+        self.stats.kv_gets += 1;
+        match kv.get(key) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(ERR_NOT_FOUND),
+            Err(e) => Err(self.io_error("get", e)),
+        }
+    }
+
+    fn kv_put(&mut self, key: &[u8], value: &[u8]) -> i32 {
+        let kv = match self.kv_for(key) {
+            Ok(kv) => kv,
+            Err(code) => return code,
+        };
+        if value.len() > self.limits.max_value_bytes {
+            return self.reject(ERR_TOO_LARGE);
+        }
+        // Quota counts the namespace as it would be after the write.
+        let usage = kv.used_bytes().and_then(|used| {
+            let replaced = kv.get(key)?.map(|old| (key.len() + old.len()) as u64).unwrap_or(0);
+            Ok(used.saturating_sub(replaced) + (key.len() + value.len()) as u64)
+        });
+        match usage {
+            Ok(after) if after > self.limits.max_total_bytes => return self.reject(ERR_QUOTA),
+            Ok(_) => {}
+            Err(e) => return self.io_error("put", e),
+        }
+        self.stats.kv_puts += 1;
+        match kv.put(key, value) {
+            Ok(()) => OK,
+            Err(e) => self.io_error("put", e),
+        }
+    }
+
+    fn kv_delete(&mut self, key: &[u8]) -> i32 {
+        let kv = match self.kv_for(key) {
+            Ok(kv) => kv,
+            Err(code) => return code,
+        };
+        self.stats.kv_deletes += 1;
+        match kv.delete(key) {
+            Ok(existed) => existed as i32,
+            Err(e) => self.io_error("delete", e),
+        }
+    }
+
+    fn io_error(&self, op: &str, e: anyhow::Error) -> i32 {
+        eprintln!("⚠️  {}: kv {} failed: {:#}", self.tenant, op, e);
+        ERR_IO
+    }
+}
+
+/// Copy `len` bytes at `ptr` out of the guest's exported memory.
+fn read<W>(caller: &mut Caller<'_, HostState<W>>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = caller.get_export("memory")?.into_memory()?;
+    let start = ptr as u32 as usize;
+    let end = start.checked_add(len as u32 as usize)?;
+    memory.data(&caller).get(start..end).map(|b| b.to_vec())
+}
+
+fn write<W>(caller: &mut Caller<'_, HostState<W>>, ptr: i32, bytes: &[u8]) -> Option<()> {
+    let memory = caller.get_export("memory")?.into_memory()?;
+    memory.write(caller, ptr as u32 as usize, bytes).ok()
+}
+
+pub(crate) fn add_to_linker<W: 'static>(linker: &mut Linker<HostState<W>>) -> Result<()> {
+    linker.func_wrap(MODULE, "log", |mut caller: Caller<'_, HostState<W>>, level: i32, ptr: i32, len: i32| {
+        match read(&mut caller, ptr, len) {
+            Some(message) => caller.data_mut().api.log(level, &message),
+            None => caller.data_mut().api.reject(ERR_INVALID),
+        }
+    })?;
+
+    linker.func_wrap(
+        MODULE,
+        "kv_get",
+        |mut caller: Caller<'_, HostState<W>>, key_ptr: i32, key_len: i32, buf_ptr: i32, buf_cap: i32| {
+            let Some(key) = read(&mut caller, key_ptr, key_len) else {
+                return caller.data_mut().api.reject(ERR_INVALID);
+            };
+            let value = match caller.data_mut().api.kv_get(&key) {
+                Ok(value) => value,
+                Err(code) => return code,
+            };
+            // Too small a buffer: report the size so the guest can retry.
+            if value.len() <= buf_cap as u32 as usize && write(&mut caller, buf_ptr, &value).is_none() {
+                return caller.data_mut().api.reject(ERR_INVALID);
+            }
+            value.len() as i32
+        },
+    )?;
+
+    linker.func_wrap(
+        MODULE,
+        "kv_put",
+        |mut caller: Caller<'_, HostState<W>>, key_ptr: i32, key_len: i32, val_ptr: i32, val_len: i32| {
+            match (read(&mut caller, key_ptr, key_len), read(&mut caller, val_ptr, val_len)) {
+                (Some(key), Some(value)) => caller.data_mut().api.kv_put(&key, &value),
+                _ => caller.data_mut().api.reject(ERR_INVALID),
+            }
+        },
+    )?;
+
+    linker.func_wrap(MODULE, "kv_delete", |mut caller: Caller<'_, HostState<W>>, key_ptr: i32, key_len: i32| {
+        match read(&mut caller, key_ptr, key_len) {
+            Some(key) => caller.data_mut().api.kv_delete(&key),
+            None => caller.data_mut().api.reject(ERR_INVALID),
+        }
+    })?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use anyhow::{anyhow, Result};
+use nc_exec::{WasmType, WasmValue};
+use wasmtime::component::{self, types::Type};
+use wasmtime::{FuncType, Val, ValType};
+
+/// Type-check `args` against a core export's signature and lower them.
+pub fn core_args(export: &str, ty: &FuncType, args: &[WasmValue]) -> Result<Vec<Val>> {
+    let expected: Vec<Option<WasmType>> = ty.params().map(|p| core_type(&p)).collect();
+    if expected.len() != args.len() || expected.iter().zip(args).any(|(e, a)| *e != Some(a.ty())) {
+        let names: Vec<String> = ty.params().map(|p| p.to_string()).collect();
+        return Err(mismatch(export, &names, args));
+    }
+    Ok(args.iter().map(|a| match *a {
+        WasmValue::I32(v) => Val::I32(v),
+        WasmValue::I64(v) => Val::I64(v),
+        WasmValue::F32(v) => Val::F32(v.to_bits()),
+        WasmValue::F64(v) => Val::F64(v.to_bits()),
+    }).collect())
+}
+
+/// Placeholder result slots for a core call.
+pub fn core_result_slots(ty: &FuncType) -> Vec<Val> {
+    vec![Val::I32(0); ty.results().len()]
+}
+
+pub fn core_results(export: &str, vals: &[Val]) -> Result<Vec<WasmValue>> {
+    vals.iter().map(|v| match v {
+        Val::I32(v) => Ok(WasmValue::I32(*v)),
+        Val::I64(v) => Ok(WasmValue::I64(*v)),
+        Val::F32(bits) => Ok(WasmValue::F32(f32::from_bits(*bits))),
+        Val::F64(bits) => Ok(WasmValue::F64(f64::from_bits(*bits))),
+        other => Err(anyhow!("'{}' returned an unsupported value {:?}", export, other)),
+    }).collect()
+}
+
+fn core_type(ty: &ValType) -> Option<WasmType> {
+    match ty {
+        ValType::I32 => Some(WasmType::I32),
+        ValType::I64 => Some(WasmType::I64),
+        ValType::F32 => Some(WasmType::F32),
+        ValType::F64 => Some(WasmType::F64),
+        _ => None,
+    }
+}
+
+/// Component exports: i32/i64/f32/f64 map onto s32/s64/float32/float64.
+pub fn component_args(export: &str, params: &[(String, Type)], args: &[WasmValue]) -> Result<Vec<component::Val>> {
+    let expected: Vec<Option<WasmType>> = params.iter().map(|(_, t)| component_type(t)).collect();
+    if expected.len() != args.len() || expected.iter().zip(args).any(|(e, a)| *e != Some(a.ty())) {
+        let names: Vec<String> = params.iter().map(|(_, t)| component_type_name(t)).collect();
+        return Err(mismatch(export, &names, args));
+    }
+    Ok(args.iter().map(|a| match *a {
+        WasmValue::I32(v) => component::Val::S32(v),
+        WasmValue::I64(v) => component::Val::S64(v),
+        WasmValue::F32(v) => component::Val::Float32(v),
+        WasmValue::F64(v) => component::Val::Float64(v),
+    }).collect())
+}
+
+pub fn component_results(export: &str, vals: &[component::Val]) -> Result<Vec<WasmValue>> {
+    vals.iter().map(|v| match v {
+        component::Val::S32(v) => Ok(WasmValue::I32(*v)),
+        component::Val::S64(v) => Ok(WasmValue::I64(*v)),
+        component::Val::Float32(v) => Ok(WasmValue::F32(*v)),
+        component::Val::Float64(v) => Ok(WasmValue::F64(*v)),
+        other => Err(anyhow!("'{}' returned an unsupported value {:?}", export, other)),
+    }).collect()
+}
+
+fn component_type(ty: &Type) -> Option<WasmType> {
+    match ty {
+        Type::S32 => Some(WasmType::I32),
+        Type::S64 => Some(WasmType::I64),
+        Type::Float32 => Some(WasmType::F32),
+        Type::Float64 => Some(WasmType::F64),
+        _ => None,
+    }
+}
+
+fn component_type_name(ty: &Type) -> String {
+    match component_type(ty) {
+        Some(t) => t.to_string(),
+        None => format!("{:?}", ty).to_lowercase(),
+    }
+}
+
+fn mismatch(export: &str, expected: &[String], args: &[WasmValue]) -> anyhow::Error {
+    let got: Vec<String> = args.iter().map(|a| a.ty().to_string()).collect();
+    anyhow!("'{}' expects ({}), got ({})", export, expected.join(", "), got.join(", "))
+}
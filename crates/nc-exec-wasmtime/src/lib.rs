@@ -1,82 +1,341 @@
 use anyhow::{anyhow, Context, Result};
 use chrono::Utc;
-use ed25519_dalek::{Signature, VerifyingKey, Verifier};
-use nc_exec::{ExecConfig, ExecProof, SandboxBackend};
+use async_trait::async_trait;
+use nc_exec::{
+    AsyncSandboxBackend, Capabilities, ExecConfig, ExecOutcome, ExecProof, HostApiStats, Invocation,
+    SandboxBackend, WasmValue,
+};
 use sha2::{Digest, Sha256};
-use std::{convert::TryInto, fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use wasmtime::{Engine, Linker, Module, Store, Val};
-use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};        // ✅ root builder + perms
-use wasmtime_wasi::p1::{add_to_linker_sync, WasiP1Ctx};           // ✅ P1 linker + context
-use base64::{engine::general_purpose::STANDARD, Engine as Base64Engine};
+use wasmtime::component::{self, Component, ResourceTable};
+use wasmtime::{Engine, Linker, Module, Store, Trap, UpdateDeadline};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView}; // ✅ root builder + perms
+use wasmtime_wasi::p1::WasiP1Ctx;                                   // ✅ P1 context
+use wasmtime_wasi::p2::bindings::{sync::Command, Command as AsyncCommand}; // ✅ WASI 0.2 command world
+use wasmtime_wasi::p2::pipe::{ClosedOutputStream, MemoryInputPipe};
 
-pub struct WasmtimeBackend;
+mod cache;
+mod determinism;
+mod engine;
+mod host_api;
+mod invoke;
+mod limiter;
+mod output;
+pub use cache::ModuleCache;
+pub use engine::{EngineOptions, EPOCH_TICK_MS};
+use engine::{Flavor, SharedEngine};
+use host_api::{HostApi, LogEntry};
+use limiter::TenantLimiter;
+use output::{sanitize, CapturePipe};
 
-impl SandboxBackend for WasmtimeBackend {
-    fn name(&self) -> &'static str { "wasmtime" }
+/// Per-store host data: the WASI context (P1 or P2), the tenant's resource
+/// limiter and the `nightcore` host module state.
+struct HostState<W> {
+    wasi: W,
+    limiter: TenantLimiter,
+    api: HostApi,
+}
+
+/// WASI 0.2 state for components: context + resource table.
+struct WasiP2 {
+    ctx: WasiCtx,
+    table: ResourceTable,
+}
+
+impl WasiView for HostState<WasiP2> {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView { ctx: &mut self.wasi.ctx, table: &mut self.wasi.table }
+    }
+}
+
+/// Guest ABI, detected from the binary header.
+#[derive(Clone, Copy, PartialEq)]
+enum Abi {
+    /// Core module against `wasi_snapshot_preview1`.
+    WasiP1,
+    /// Component against the WASI 0.2 `wasi:cli/command` world.
+    WasiP2,
+}
+
+impl Abi {
+    fn detect(bytes: &[u8]) -> Self {
+        // Binary: the layer field after `\0asm` is 0x0d 0x00 0x01 0x00 for components.
+        // Text (WAT) is accepted for modules too, so sniff its first form.
+        let text_component = std::str::from_utf8(bytes)
+            .map(|t| t.trim_start().starts_with("(component"))
+            .unwrap_or(false);
+        let binary_component = bytes.starts_with(b"\0asm") && bytes.get(4..8) == Some(&[0x0d, 0x00, 0x01, 0x00]);
+        if binary_component || text_component {
+            Abi::WasiP2
+        } else {
+            Abi::WasiP1
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Abi::WasiP1 => "wasi_p1",
+            Abi::WasiP2 => "wasi_p2",
+        }
+    }
+}
+
+/// What a guest run left behind, independent of ABI.
+struct RunStats {
+    /// Entrypoint results (empty for `_start` / `wasi:cli/run`).
+    result: Result<Vec<WasmValue>>,
+    fuel_consumed: Option<u64>,
+    peak_memory_bytes: u64,
+    host_api: HostApiStats,
+    guest_log: Vec<LogEntry>,
+}
+
+/// Wasmtime sandbox. Holds one long-lived engine shared by every run (and
+/// thread), so compiled code, the epoch ticker and any instance pool are set
+/// up once per process instead of once per tenant.
+pub struct WasmtimeBackend {
+    opts: EngineOptions,
+    /// One engine per flavor; the plain sync engine is built up front, the
+    /// others (async, deterministic, per compiler profile) on first use.
+    engines: Mutex<HashMap<Flavor, SharedEngine>>,
+    cache: Option<Arc<ModuleCache>>,
+}
+
+impl WasmtimeBackend {
+    pub fn new(opts: &EngineOptions) -> Result<Self> {
+        let engine = engine::build_engine(opts, Flavor::default())?;
+        Ok(Self {
+            opts: opts.clone(),
+            engines: Mutex::new(HashMap::from([(Flavor::default(), engine)])),
+            cache: None,
+        })
+    }
 
-    fn verify(&self, module_path: &Path) -> Result<()> {
-        let dir = module_path.parent().ok_or_else(|| anyhow!("No parent dir"))?;
-        let wasm = fs::read(module_path).context("Read module")?;
-        let digest = Sha256::digest(&wasm);
-        println!("✅ SHA256 digest: {:x}", digest);
+    fn engine(&self, cfg: &ExecConfig, async_support: bool) -> Result<SharedEngine> {
+        let flavor = Flavor { async_support, deterministic: cfg.deterministic.is_some(), compiler: cfg.compiler };
+        let mut engines = self.engines.lock().unwrap();
+        if let Some(engine) = engines.get(&flavor) {
+            return Ok(engine.clone());
+        }
+        let engine = engine::build_engine(&self.opts, flavor)
+            .with_context(|| format!("building engine for {}", cfg.tenant))?;
+        engines.insert(flavor, engine.clone());
+        Ok(engine)
+    }
 
-        // --- Ed25519 signature verification
-        let pk_b64 = fs::read_to_string(dir.join("pubkey.b64")).context("pubkey.b64")?;
-        let sig_b64 = fs::read_to_string(dir.join("module.sig")).context("module.sig")?;
-        let sig_bytes = STANDARD.decode(sig_b64.trim()).context("Signature not valid base64")?;
+    /// Keep precompiled `.cwasm` artifacts of verified modules under `dir`.
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(Arc::new(ModuleCache::new(dir)));
+        self
+    }
 
-        let pk_vec = STANDARD.decode(pk_b64.trim()).context("Invalid base64 pubkey")?;
-        let pk: [u8; 32] = pk_vec.try_into().map_err(|_| anyhow!("Pubkey len != 32"))?;
-        let sig_arr: [u8; 64] = sig_bytes.try_into().map_err(|_| anyhow!("Signature len != 64"))?;
+    /// Compile `bytes`, through the artifact cache when one is configured.
+    fn load<A: cache::Artifact>(cache: Option<&ModuleCache>, engine: &SharedEngine, sha256: &str, bytes: &[u8]) -> Result<A> {
+        match cache {
+            Some(cache) => cache.load(&engine.engine, &engine.fingerprint, sha256, bytes),
+            None => A::compile(&engine.engine, bytes),
+        }
+    }
 
-        let vk = VerifyingKey::from_bytes(&pk).context("Bad pubkey")?;
-        let sig = Signature::from_bytes(&sig_arr);
-        vk.verify(&wasm, &sig).context("Signature verify failed")?;
-        println!("✅ Ed25519 signature verified");
-        Ok(())
+    /// The blocking half of starting a run: read and check the module, build
+    /// its WASI context and compile it.
+    fn setup(cache: Option<&ModuleCache>, engine: &SharedEngine, cfg: &ExecConfig) -> Result<(PreparedRun, Compiled)> {
+        let run = Self::prepare(cfg)?;
+        let compiled = match run.abi {
+            Abi::WasiP1 => Compiled::Module(Self::load(cache, engine, &run.hash, &run.bytes)?),
+            Abi::WasiP2 => Compiled::Component(Self::load(cache, engine, &run.hash, &run.bytes)?),
+        };
+        Ok((run, compiled))
+    }
+}
+
+impl SandboxBackend for WasmtimeBackend {
+    fn name(&self) -> &'static str { "wasmtime" }
+
+    fn verify(&self, module_path: &Path, capabilities: &Capabilities) -> Result<String> {
+        let sha256 = nc_exec::default_verify(module_path, capabilities)?;
+        if let Some(cache) = &self.cache {
+            cache.mark_verified(&sha256);
+        }
+        Ok(sha256)
     }
 
     fn execute(&self, cfg: &ExecConfig) -> Result<ExecProof> {
-        let started = Utc::now().to_rfc3339();
+        let shared = self.engine(cfg, false)?;
+        let engine = &shared.engine;
+        let (mut run, compiled) = Self::setup(self.cache.as_deref(), &shared, cfg)?;
+        let stats = match compiled {
+            Compiled::Module(module) => run_module(engine, cfg, &module, run.builder.build_p1())?,
+            Compiled::Component(component) => {
+                let wasi = WasiP2 { ctx: run.builder.build(), table: ResourceTable::new() };
+                run_component(engine, cfg, &component, wasi)?
+            }
+        };
+        Self::finish(cfg, run, stats, &shared.fingerprint)
+    }
+}
 
-        // --- Engine + module
-        let engine = Engine::default();
-        let module = Module::from_file(&engine, &cfg.module_path)?;
+#[async_trait]
+impl AsyncSandboxBackend for WasmtimeBackend {
+    async fn execute_async(&self, cfg: &ExecConfig) -> Result<ExecProof> {
+        let shared = self.engine(cfg, true)?;
+        // Module reads, preopens, compilation and the log/proof writes all
+        // block, so they run on the blocking pool rather than the executor.
+        let owned = Arc::new(cfg.clone());
+        let (mut run, compiled) = {
+            let (cache, shared, cfg) = (self.cache.clone(), shared.clone(), Arc::clone(&owned));
+            blocking(move || Self::setup(cache.as_deref(), &shared, &cfg)).await?
+        };
+        let engine = &shared.engine;
+        let stats = match compiled {
+            Compiled::Module(module) => run_module_async(engine, cfg, &module, run.builder.build_p1()).await?,
+            Compiled::Component(component) => {
+                let wasi = WasiP2 { ctx: run.builder.build(), table: ResourceTable::new() };
+                run_component_async(engine, cfg, &component, wasi).await?
+            }
+        };
+        let fingerprint = shared.fingerprint.clone();
+        blocking(move || Self::finish(&owned, run, stats, &fingerprint)).await
+    }
+}
+
+/// Run blocking work on tokio's blocking pool.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(work).await.map_err(|e| anyhow!("blocking task failed: {}", e))?
+}
+
+/// A compiled guest, by ABI.
+enum Compiled {
+    Module(Module),
+    Component(Component),
+}
 
-        // --- Build WASI P1 context using the public v37 API
+/// Everything set up before the guest runs, shared by the sync and async paths.
+struct PreparedRun {
+    started: String,
+    bytes: Vec<u8>,
+    abi: Abi,
+    hash: String,
+    builder: WasiCtxBuilder,
+    stdout: CapturePipe,
+    stderr: CapturePipe,
+}
+
+impl WasmtimeBackend {
+    /// Read the module (refusing bytes other than the verified ones) and
+    /// build its WASI context from the tenant's capabilities.
+    fn prepare(cfg: &ExecConfig) -> Result<PreparedRun> {
+        let started = Utc::now().to_rfc3339();
+        let bytes = fs::read(&cfg.module_path)
+            .with_context(|| format!("reading {}", cfg.module_path.display()))?;
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        cfg.check_verified(&hash)?;
+        let abi = Abi::detect(&bytes);
+
+        // --- Build the WASI context using the public v37 API (shared by P1 and P2)
         let mut builder = WasiCtxBuilder::new();
-        for dir in &cfg.preopen_dirs {
-            // ✅ ensure directory exists before preopening
-            fs::create_dir_all(dir)
-                .with_context(|| format!("creating preopen dir: {}", dir.display()))?;
+        let caps = &cfg.capabilities;
+        for mount in &cfg.mounts {
+            // Mounts without a matching fs:read / fs:write permission are never preopened.
+            let Some(cap_read_only) = caps.mount_access(&mount.guest_path) else {
+                println!("🚫 {}: no fs permission for {}, mount skipped", cfg.tenant, mount.guest_path);
+                continue;
+            };
+            let (dir_perms, file_perms) = if mount.read_only || cap_read_only {
+                (DirPerms::READ, FilePerms::READ)
+            } else {
+                // ✅ writable mounts are created on demand
+                fs::create_dir_all(&mount.host_path)
+                    .with_context(|| format!("creating mount dir: {}", mount.host_path.display()))?;
+                (DirPerms::all(), FilePerms::all())
+            };
 
             builder
-                .preopened_dir(
-                    dir,                                   // host path (Path)
-                    dir.to_string_lossy(),                 // guest path (str)
-                    DirPerms::all(),                       // directory permissions
-                    FilePerms::all(),                      // file permissions
-                )
-                .context("Preopen directory failed")?;
+                .preopened_dir(&mount.host_path, &mount.guest_path, dir_perms, file_perms)
+                .with_context(|| format!(
+                    "Preopen {} → {} failed", mount.host_path.display(), mount.guest_path
+                ))?;
         }
 
-        // ✅ Build WASI P1 context
-        let wasi_p1: WasiP1Ctx = builder.build_p1();
+        // --- Environment (only with `env`) + argv; a deterministic run never
+        // sees the host environment.
+        if caps.env {
+            if cfg.inherit_env && cfg.deterministic.is_none() {
+                builder.inherit_env();
+            }
+            builder.envs(&cfg.env);
+        }
+        builder.arg(&cfg.tenant).args(&cfg.args);
 
-        // --- Store + linker + run (P1 expects &mut WasiP1Ctx)
-        let mut store = Store::new(&engine, wasi_p1);
-        let mut linker = Linker::new(&engine);
-        add_to_linker_sync(&mut linker, |cx: &mut WasiP1Ctx| cx)?; // ✅ matches trait bound
+        // --- Guest stdio goes to bounded per-tenant buffers, never the host terminal;
+        // a stream without its permission is closed outright.
+        let stdout = CapturePipe::new(cfg.output.max_stdout_bytes);
+        let stderr = CapturePipe::new(cfg.output.max_stderr_bytes);
+        if let Some(payload) = &cfg.stdin {
+            builder.stdin(MemoryInputPipe::new(payload.clone()));
+        }
+        if caps.stdout {
+            builder.stdout(stdout.clone());
+        } else {
+            builder.stdout(ClosedOutputStream);
+        }
+        if caps.stderr {
+            builder.stderr(stderr.clone());
+        } else {
+            builder.stderr(ClosedOutputStream);
+        }
 
-        let instance = linker.instantiate(&mut store, &module)?;
-        if let Some(start) = instance.get_func(&mut store, "_start") {
-            let mut results: Vec<Val> = vec![];
-            start.call(&mut store, &[], &mut results)?; // ✅ Wasmtime v37 call signature
+        // --- Network only with `net`, and never for deterministic runs
+        if let Some(profile) = &cfg.deterministic {
+            determinism::apply(&mut builder, profile);
         }
+        if caps.net && cfg.deterministic.is_none() {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        } else {
+            builder.allow_tcp(false).allow_udp(false).allow_ip_name_lookup(false);
+        }
+
+        Ok(PreparedRun { started, bytes, abi, hash, builder, stdout, stderr })
+    }
+
+    /// Classify the run, persist captured output and write the proof.
+    fn finish(cfg: &ExecConfig, run: PreparedRun, stats: RunStats, fingerprint: &str) -> Result<ExecProof> {
+        let PreparedRun { started, abi, hash, stdout, stderr, .. } = run;
+        let (outcome, results) = match stats.result {
+            Ok(results) => (ExecOutcome::Exited { code: 0 }, results),
+            Err(e) => (classify(&e), vec![]),
+        };
+        let invocation = (cfg.entrypoint != "_start").then(|| Invocation {
+            export: cfg.entrypoint.clone(),
+            args: cfg.call_args.clone(),
+            results,
+        });
 
         let finished = Utc::now().to_rfc3339();
-        let hash = format!("{:x}", Sha256::digest(fs::read(&cfg.module_path)?));
+
+        let dir = format!("logs/{}/", cfg.tenant);
+        fs::create_dir_all(&dir)?;
+
+        // --- Persist captured streams; their digests become part of the proof
+        let (out_bytes, out_truncated) = stdout.contents();
+        let (err_bytes, err_truncated) = stderr.contents();
+        fs::write(format!("{}stdout.log", dir), &out_bytes)?;
+        fs::write(format!("{}stderr.log", dir), &err_bytes)?;
+        let mut guest_log = String::new();
+        for entry in &stats.guest_log {
+            guest_log += &(serde_json::to_string(entry)? + "\n");
+        }
+        fs::write(format!("{}guest_log.jsonl", dir), guest_log)?;
+        if cfg.output.echo {
+            print_streams(&cfg.tenant, &out_bytes, &err_bytes);
+        }
 
         // --- Proof output
         let proof = ExecProof {
@@ -85,12 +344,23 @@ impl SandboxBackend for WasmtimeBackend {
             signer_key_b64: "<verified>".into(),
             started_at: started.clone(),
             finished_at: finished.clone(),
-            status: "ok".into(),
-            backend: self.name().into(),
+            status: outcome.status().into(),
+            outcome,
+            backend: "wasmtime".into(),
+            abi: Some(abi.as_str().into()),
+            fuel_consumed: stats.fuel_consumed,
+            peak_memory_bytes: Some(stats.peak_memory_bytes),
+            stdout_sha256: Some(format!("{:x}", Sha256::digest(&out_bytes))),
+            stderr_sha256: Some(format!("{:x}", Sha256::digest(&err_bytes))),
+            output_truncated: out_truncated || err_truncated,
+            invocation,
+            determinism: cfg.deterministic.clone(),
+            host_api: (stats.host_api != HostApiStats::default()).then_some(stats.host_api),
+            stdin_sha256: cfg.stdin.as_ref().map(|p| format!("{:x}", Sha256::digest(p))),
+            compiler: Some(cfg.compiler),
+            engine_fingerprint: Some(fingerprint.to_string()),
+            stdout: out_bytes,
         };
-
-        let dir = format!("logs/{}/", cfg.tenant);
-        fs::create_dir_all(&dir)?;
         fs::write(
             format!("{}proof_report.jsonl", dir),
             serde_json::to_string(&proof)? + "\n",
@@ -101,13 +371,269 @@ impl SandboxBackend for WasmtimeBackend {
                 r#"
 <!doctype html><html><meta charset="utf-8"><body>
 <h3>Tenant {}</h3>
-<p>SHA256 {} ✅<br/>Backend Wasmtime</p>
+<p>SHA256 {} ✅<br/>Backend Wasmtime ({}, engine {})</p>
 <p>Started {}<br/>Finished {}</p>
 </body></html>"#,
-                cfg.tenant, hash, started, finished
+                cfg.tenant, hash, abi.as_str(), fingerprint, started, finished
             ),
         )?;
 
         Ok(proof)
     }
 }
+
+/// Core module path: `wasi_snapshot_preview1` imports, entrypoint export.
+fn run_module(engine: &Engine, cfg: &ExecConfig, module: &Module, wasi: WasiP1Ctx) -> Result<RunStats> {
+    let mut store = tenant_store(engine, cfg, wasi)?;
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |host: &mut HostState<WasiP1Ctx>| &mut host.wasi)?;
+    deny_unpermitted(&mut linker, &cfg.capabilities)?;
+    host_api::add_to_linker(&mut linker)?;
+
+    // Instantiation can run a start section, so it shares the fuel budget.
+    let result = (|| -> Result<Vec<WasmValue>> {
+        let instance = linker.instantiate(&mut store, module)?;
+        match instance.get_func(&mut store, &cfg.entrypoint) {
+            Some(entry) => {
+                let ty = entry.ty(&store);
+                let params = invoke::core_args(&cfg.entrypoint, &ty, &cfg.call_args)?;
+                let mut results = invoke::core_result_slots(&ty);
+                entry.call(&mut store, &params, &mut results)?; // ✅ Wasmtime v37 call signature
+                invoke::core_results(&cfg.entrypoint, &results)
+            }
+            None => Err(MissingEntrypoint(cfg.entrypoint.clone()).into()),
+        }
+    })();
+    Ok(run_stats(cfg, &store, result))
+}
+
+/// Component path: WASI 0.2 imports; `_start` maps to `wasi:cli/run`, any
+/// other entrypoint to a top-level exported function without parameters.
+fn run_component(engine: &Engine, cfg: &ExecConfig, component: &Component, wasi: WasiP2) -> Result<RunStats> {
+    let mut store = tenant_store(engine, cfg, wasi)?;
+    let mut linker = component::Linker::new(engine);
+    wasmtime_wasi::p2::add_to_linker_sync(&mut linker)?;
+    deny_unpermitted_p2(&mut linker, &cfg.capabilities)?;
+
+    let result = (|| -> Result<Vec<WasmValue>> {
+        if cfg.entrypoint == "_start" {
+            let command = Command::instantiate(&mut store, component, &linker)?;
+            // `run` returning `err` is the component equivalent of exit(1).
+            return command.wasi_cli_run().call_run(&mut store)?
+                .map(|()| vec![])
+                .map_err(|()| I32Exit(1).into());
+        }
+        let instance = linker.instantiate(&mut store, component)?;
+        let entry = instance.get_func(&mut store, &cfg.entrypoint)
+            .ok_or_else(|| MissingEntrypoint(cfg.entrypoint.clone()))?;
+        let params = invoke::component_args(&cfg.entrypoint, &entry.params(&store), &cfg.call_args)?;
+        let mut results = vec![component::Val::Bool(false); entry.results(&store).len()];
+        entry.call(&mut store, &params, &mut results)?;
+        entry.post_return(&mut store)?;
+        invoke::component_results(&cfg.entrypoint, &results)
+    })();
+    Ok(run_stats(cfg, &store, result))
+}
+
+/// Async core module path: same as [`run_module`], but the guest yields to
+/// the executor on every epoch tick and fuel interval.
+async fn run_module_async(engine: &Engine, cfg: &ExecConfig, module: &Module, wasi: WasiP1Ctx) -> Result<RunStats> {
+    let mut store = async_tenant_store(engine, cfg, wasi)?;
+    let mut linker = Linker::new(engine);
+    wasmtime_wasi::p1::add_to_linker_async(&mut linker, |host: &mut HostState<WasiP1Ctx>| &mut host.wasi)?;
+    deny_unpermitted(&mut linker, &cfg.capabilities)?;
+    host_api::add_to_linker(&mut linker)?;
+
+    let result = async {
+        let instance = linker.instantiate_async(&mut store, module).await?;
+        match instance.get_func(&mut store, &cfg.entrypoint) {
+            Some(entry) => {
+                let ty = entry.ty(&store);
+                let params = invoke::core_args(&cfg.entrypoint, &ty, &cfg.call_args)?;
+                let mut results = invoke::core_result_slots(&ty);
+                entry.call_async(&mut store, &params, &mut results).await?;
+                invoke::core_results(&cfg.entrypoint, &results)
+            }
+            None => Err(MissingEntrypoint(cfg.entrypoint.clone()).into()),
+        }
+    }.await;
+    Ok(run_stats(cfg, &store, result))
+}
+
+/// Async component path, mirroring [`run_component`].
+async fn run_component_async(engine: &Engine, cfg: &ExecConfig, component: &Component, wasi: WasiP2) -> Result<RunStats> {
+    let mut store = async_tenant_store(engine, cfg, wasi)?;
+    let mut linker = component::Linker::new(engine);
+    wasmtime_wasi::p2::add_to_linker_async(&mut linker)?;
+    deny_unpermitted_p2(&mut linker, &cfg.capabilities)?;
+
+    let result = async {
+        if cfg.entrypoint == "_start" {
+            let command = AsyncCommand::instantiate_async(&mut store, component, &linker).await?;
+            return command.wasi_cli_run().call_run(&mut store).await?
+                .map(|()| vec![])
+                .map_err(|()| I32Exit(1).into());
+        }
+        let instance = linker.instantiate_async(&mut store, component).await?;
+        let entry = instance.get_func(&mut store, &cfg.entrypoint)
+            .ok_or_else(|| MissingEntrypoint(cfg.entrypoint.clone()))?;
+        let params = invoke::component_args(&cfg.entrypoint, &entry.params(&store), &cfg.call_args)?;
+        let mut results = vec![component::Val::Bool(false); entry.results(&store).len()];
+        entry.call_async(&mut store, &params, &mut results).await?;
+        entry.post_return_async(&mut store).await?;
+        invoke::component_results(&cfg.entrypoint, &results)
+    }.await;
+    Ok(run_stats(cfg, &store, result))
+}
+
+/// Fuel burned between cooperative yields on the async path.
+const FUEL_YIELD_INTERVAL: u64 = 100_000;
+
+/// [`tenant_store`] for the async engine: the guest also yields back to the
+/// executor on every epoch tick and fuel interval.
+fn async_tenant_store<W: Send + 'static>(engine: &Engine, cfg: &ExecConfig, wasi: W) -> Result<Store<HostState<W>>> {
+    let mut store = tenant_store(engine, cfg, wasi)?;
+    store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL))?;
+    let interrupt = interrupt_check(cfg);
+    store.epoch_deadline_callback(move |_| {
+        Ok(if interrupt() { UpdateDeadline::Interrupt } else { UpdateDeadline::Yield(1) })
+    });
+    Ok(store)
+}
+
+/// Store with the tenant's limiter installed, fuel budget loaded and
+/// wall-clock deadline / cancellation checked on every shared-engine epoch tick.
+fn tenant_store<W>(engine: &Engine, cfg: &ExecConfig, wasi: W) -> Result<Store<HostState<W>>> {
+    let host = HostState { wasi, limiter: TenantLimiter::new(&cfg.limits), api: HostApi::new(cfg) };
+    let mut store = Store::new(engine, host);
+    store.limiter(|host| &mut host.limiter);
+    store.set_fuel(cfg.fuel.unwrap_or(u64::MAX))?;
+    store.set_epoch_deadline(1);
+    let interrupt = interrupt_check(cfg);
+    store.epoch_deadline_callback(move |_| {
+        Ok(if interrupt() { UpdateDeadline::Interrupt } else { UpdateDeadline::Continue(1) })
+    });
+    Ok(store)
+}
+
+/// True once the run's wall-clock budget is spent or it has been cancelled.
+fn interrupt_check(cfg: &ExecConfig) -> impl Fn() -> bool + Send + Sync + 'static {
+    let deadline = cfg.time_limit_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+    let cancel = cfg.cancel.clone();
+    move || cancel.is_cancelled() || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+fn run_stats<W>(cfg: &ExecConfig, store: &Store<HostState<W>>, result: Result<Vec<WasmValue>>) -> RunStats {
+    RunStats {
+        result,
+        fuel_consumed: cfg.fuel.map(|budget| budget - store.get_fuel().unwrap_or(0)),
+        peak_memory_bytes: store.data().limiter.peak_memory_bytes(),
+        host_api: store.data().api.stats.clone(),
+        guest_log: store.data().api.entries.clone(),
+    }
+}
+
+/// WASI errno returned by calls whose capability was not granted.
+const ERRNO_NOTCAPABLE: i32 = 76;
+
+/// Replace clock / random imports with stubs that fail with `notcapable`
+/// unless the tenant holds the matching permission.
+fn deny_unpermitted(linker: &mut Linker<HostState<WasiP1Ctx>>, caps: &Capabilities) -> Result<()> {
+    const WASI: &str = "wasi_snapshot_preview1";
+    linker.allow_shadowing(true);
+    if !caps.clock {
+        linker.func_wrap(WASI, "clock_time_get", |_: i32, _: i64, _: i32| ERRNO_NOTCAPABLE)?;
+        linker.func_wrap(WASI, "clock_res_get", |_: i32, _: i32| ERRNO_NOTCAPABLE)?;
+    }
+    if !caps.random {
+        linker.func_wrap(WASI, "random_get", |_: i32, _: i32| ERRNO_NOTCAPABLE)?;
+    }
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+/// WASI 0.2 interface version provided by wasmtime-wasi 37; guest imports of
+/// any compatible 0.2.x version resolve to it.
+const WASI_P2_VERSION: &str = "0.2.6";
+
+/// P2 clock / random calls have no error channel, so without the permission
+/// the call aborts the run instead of returning `notcapable`.
+fn deny_unpermitted_p2(linker: &mut component::Linker<HostState<WasiP2>>, caps: &Capabilities) -> Result<()> {
+    let mut denied: Vec<(&str, &[&str])> = vec![];
+    if !caps.clock {
+        denied.push(("wasi:clocks/wall-clock", &["now", "resolution"]));
+        denied.push(("wasi:clocks/monotonic-clock", &["now", "resolution", "subscribe-instant", "subscribe-duration"]));
+    }
+    if !caps.random {
+        denied.push(("wasi:random/random", &["get-random-bytes", "get-random-u64"]));
+        denied.push(("wasi:random/insecure", &["get-insecure-random-bytes", "get-insecure-random-u64"]));
+        denied.push(("wasi:random/insecure-seed", &["insecure-seed"]));
+    }
+    linker.allow_shadowing(true);
+    for (interface, funcs) in denied {
+        let mut instance = linker.instance(&format!("{}@{}", interface, WASI_P2_VERSION))?;
+        for func in funcs {
+            let name = format!("{}#{}", interface, func);
+            instance.func_new(func, move |_, _, _| Err(anyhow!("{} not permitted", name)))?;
+        }
+    }
+    linker.allow_shadowing(false);
+    Ok(())
+}
+
+/// The module or component does not export the configured entrypoint.
+#[derive(Debug)]
+struct MissingEntrypoint(String);
+
+impl std::fmt::Display for MissingEntrypoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "entrypoint '{}' is not exported", self.0)
+    }
+}
+
+impl std::error::Error for MissingEntrypoint {}
+
+/// Sort a failed run into exit / missing entrypoint / trap / host error.
+fn classify(e: &anyhow::Error) -> ExecOutcome {
+    if let Some(exit) = e.downcast_ref::<I32Exit>() {
+        return ExecOutcome::Exited { code: exit.0 };
+    }
+    if let Some(missing) = e.downcast_ref::<MissingEntrypoint>() {
+        return ExecOutcome::MissingEntrypoint { entrypoint: missing.0.clone() };
+    }
+    if let Some(trap) = e.downcast_ref::<Trap>() {
+        return ExecOutcome::Trapped { trap: trap_code(trap).into() };
+    }
+    ExecOutcome::HostError { message: format!("{:#}", e) }
+}
+
+fn trap_code(trap: &Trap) -> &'static str {
+    match trap {
+        Trap::StackOverflow => "stack_overflow",
+        Trap::MemoryOutOfBounds => "memory_out_of_bounds",
+        Trap::HeapMisaligned => "heap_misaligned",
+        Trap::TableOutOfBounds => "table_out_of_bounds",
+        Trap::IndirectCallToNull => "indirect_call_to_null",
+        Trap::BadSignature => "bad_signature",
+        Trap::IntegerOverflow => "integer_overflow",
+        Trap::IntegerDivisionByZero => "integer_division_by_zero",
+        Trap::BadConversionToInteger => "bad_conversion_to_integer",
+        Trap::UnreachableCodeReached => "unreachable",
+        Trap::Interrupt => "interrupt",
+        Trap::OutOfFuel => "out_of_fuel",
+        _ => "other",
+    }
+}
+
+/// Echo a tenant's captured output as one block so parallel runs don't interleave.
+fn print_streams(tenant: &str, stdout: &[u8], stderr: &[u8]) {
+    let mut block = String::new();
+    for (label, bytes) in [("stdout", stdout), ("stderr", stderr)] {
+        for line in sanitize(bytes).lines() {
+            block.push_str(&format!("[{}:{}] {}\n", tenant, label, line));
+        }
+    }
+    if !block.is_empty() {
+        print!("{}", block);
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use nc_exec::ResourceLimits;
+use wasmtime::ResourceLimiter;
+
+/// Per-store limiter: caps linear memory (summed across memories), table
+/// elements and instance count, and tracks peak memory for the proof.
+/// Denied growth returns `false`, so `memory.grow` yields -1 instead of trapping.
+pub struct TenantLimiter {
+    max_memory_bytes: Option<usize>,
+    max_table_elements: Option<usize>,
+    max_instances: Option<usize>,
+    memory_bytes: usize,
+    peak_memory_bytes: usize,
+}
+
+impl TenantLimiter {
+    pub fn new(limits: &ResourceLimits) -> Self {
+        Self {
+            max_memory_bytes: limits.max_memory_mb.map(|mb| (mb as usize).saturating_mul(1024 * 1024)),
+            max_table_elements: limits.max_table_elements.map(|n| n as usize),
+            max_instances: limits.max_instances.map(|n| n as usize),
+            memory_bytes: 0,
+            peak_memory_bytes: 0,
+        }
+    }
+
+    pub fn peak_memory_bytes(&self) -> u64 {
+        self.peak_memory_bytes as u64
+    }
+}
+
+impl ResourceLimiter for TenantLimiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        let total = self.memory_bytes.saturating_sub(current) + desired;
+        if matches!(self.max_memory_bytes, Some(max) if total > max) {
+            return Ok(false);
+        }
+        self.memory_bytes = total;
+        self.peak_memory_bytes = self.peak_memory_bytes.max(total);
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        Ok(!matches!(self.max_table_elements, Some(max) if desired > max))
+    }
+
+    fn instances(&self) -> usize {
+        self.max_instances.unwrap_or(10_000)
+    }
+}
@@ -0,0 +1,311 @@
+use crate::Capabilities;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use wasmparser::{Encoding, Parser, Payload, TypeRef};
+
+/// Static view of a module or component, taken before anything is compiled.
+/// Imports and exports are the top-level ones only; memories and custom
+/// sections include those of core modules nested in a component.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleReport {
+    /// `module` or `component`.
+    pub kind: &'static str,
+    pub imports: Vec<ImportEntry>,
+    pub exports: Vec<ExportEntry>,
+    pub memories: Vec<MemoryEntry>,
+    pub custom_sections: Vec<CustomSection>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportEntry {
+    /// Core module name, or the full interface name for components
+    /// (`wasi:cli/stdout@0.2.0`).
+    pub module: String,
+    /// Item name; empty for component instance imports.
+    pub name: String,
+    pub kind: String,
+}
+
+impl std::fmt::Display for ImportEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.name.is_empty() {
+            write!(f, "{} ({})", self.module, self.kind)
+        } else {
+            write!(f, "{}::{} ({})", self.module, self.name, self.kind)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportEntry {
+    pub name: String,
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryEntry {
+    pub initial_pages: u64,
+    pub maximum_pages: Option<u64>,
+    pub shared: bool,
+    pub memory64: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomSection {
+    pub name: String,
+    pub size: usize,
+}
+
+/// An import the tenant's capabilities do not cover.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportDenial {
+    pub import: ImportEntry,
+    pub reason: String,
+}
+
+/// Parse `bytes` (binary, or WAT text) without compiling or validating code.
+pub fn analyze(bytes: &[u8]) -> Result<ModuleReport> {
+    // Wasmtime `.cwasm` artifacts are ELF objects; without this they would be
+    // handed to the WAT parser and fail as invalid UTF-8.
+    if bytes.starts_with(b"\x7fELF") {
+        return Err(anyhow!("precompiled artifact, not a wasm module"));
+    }
+    let binary = wat::parse_bytes(bytes).context("module is neither wasm nor WAT")?;
+    let mut report = ModuleReport {
+        kind: "module",
+        imports: vec![],
+        exports: vec![],
+        memories: vec![],
+        custom_sections: vec![],
+    };
+    // Nested modules/components open their own Version..End span.
+    let mut depth = 0;
+    for payload in Parser::new(0).parse_all(&binary) {
+        match payload.context("malformed module")? {
+            Payload::Version { encoding, .. } => {
+                if depth == 0 && encoding == Encoding::Component {
+                    report.kind = "component";
+                }
+                depth += 1;
+            }
+            Payload::End(_) => depth -= 1,
+            Payload::ImportSection(reader) if depth == 1 => {
+                for import in reader {
+                    let import = import?;
+                    report.imports.push(ImportEntry {
+                        module: import.module.into(),
+                        name: import.name.into(),
+                        kind: type_ref_kind(&import.ty).into(),
+                    });
+                }
+            }
+            Payload::ExportSection(reader) if depth == 1 => {
+                for export in reader {
+                    let export = export?;
+                    report.exports.push(ExportEntry { name: export.name.into(), kind: format!("{:?}", export.kind).to_lowercase() });
+                }
+            }
+            Payload::ComponentImportSection(reader) if depth == 1 => {
+                for import in reader {
+                    let import = import?;
+                    report.imports.push(ImportEntry {
+                        module: import.name.0.into(),
+                        name: String::new(),
+                        kind: component_kind(&format!("{:?}", import.ty)),
+                    });
+                }
+            }
+            Payload::ComponentExportSection(reader) if depth == 1 => {
+                for export in reader {
+                    let export = export?;
+                    report.exports.push(ExportEntry { name: export.name.0.into(), kind: format!("{:?}", export.kind).to_lowercase() });
+                }
+            }
+            Payload::MemorySection(reader) => {
+                for memory in reader {
+                    let memory = memory?;
+                    report.memories.push(MemoryEntry {
+                        initial_pages: memory.initial,
+                        maximum_pages: memory.maximum,
+                        shared: memory.shared,
+                        memory64: memory.memory64,
+                    });
+                }
+            }
+            Payload::CustomSection(reader) => {
+                report.custom_sections.push(CustomSection { name: reader.name().into(), size: reader.data().len() });
+            }
+            _ => {}
+        }
+    }
+    Ok(report)
+}
+
+fn type_ref_kind(ty: &TypeRef) -> &'static str {
+    match ty {
+        TypeRef::Func(_) => "func",
+        TypeRef::Table(_) => "table",
+        TypeRef::Memory(_) => "memory",
+        TypeRef::Global(_) => "global",
+        TypeRef::Tag(_) => "tag",
+    }
+}
+
+/// `Instance(3)` → `instance`.
+fn component_kind(debug: &str) -> String {
+    debug.split('(').next().unwrap_or(debug).to_lowercase()
+}
+
+const WASI_P1: &str = "wasi_snapshot_preview1";
+
+/// Process, argv/env and fd plumbing every libc start-up pulls in. What the
+/// fds reach is still bounded by preopens and stdio permissions at run time.
+const WASI_P1_BASE: &[&str] = &[
+    "args_get", "args_sizes_get", "environ_get", "environ_sizes_get", "proc_exit", "proc_raise",
+    "sched_yield", "poll_oneoff", "fd_write", "fd_read", "fd_close", "fd_seek", "fd_tell",
+    "fd_sync", "fd_datasync", "fd_advise", "fd_allocate", "fd_fdstat_get", "fd_fdstat_set_flags",
+    "fd_fdstat_set_rights", "fd_filestat_get", "fd_prestat_get", "fd_prestat_dir_name", "fd_renumber",
+];
+const WASI_P1_FS_READ: &[&str] = &["path_open", "path_filestat_get", "path_readlink", "fd_readdir", "fd_pread"];
+const WASI_P1_FS_WRITE: &[&str] = &[
+    "path_create_directory", "path_remove_directory", "path_unlink_file", "path_rename",
+    "path_symlink", "path_link", "path_filestat_set_times", "fd_pwrite", "fd_filestat_set_size",
+    "fd_filestat_set_times",
+];
+
+/// WASI 0.2 interfaces (version stripped) granted without a permission.
+const WASI_P2_BASE: &[&str] = &[
+    "wasi:cli/environment", "wasi:cli/exit", "wasi:cli/stdin", "wasi:cli/stdout", "wasi:cli/stderr",
+    "wasi:cli/terminal-input", "wasi:cli/terminal-output", "wasi:cli/terminal-stdin",
+    "wasi:cli/terminal-stdout", "wasi:cli/terminal-stderr", "wasi:io/error", "wasi:io/poll",
+    "wasi:io/streams",
+];
+
+impl ModuleReport {
+    /// Deny-by-default import check: anything not known to be covered by
+    /// `caps` is reported, including imports from modules no host provides.
+    pub fn denied_imports(&self, caps: &Capabilities) -> Vec<ImportDenial> {
+        self.imports
+            .iter()
+            .filter_map(|import| {
+                let denied = if self.kind == "component" {
+                    component_import_denial(import, caps)
+                } else {
+                    core_import_denial(import, caps)
+                };
+                denied.map(|reason| ImportDenial { import: import.clone(), reason })
+            })
+            .collect()
+    }
+}
+
+fn core_import_denial(import: &ImportEntry, caps: &Capabilities) -> Option<String> {
+    if import.kind != "func" {
+        return Some(format!("{} imports are not provided by the host", import.kind));
+    }
+    let fs_any = !caps.fs_read.is_empty() || !caps.fs_write.is_empty();
+    let name = import.name.as_str();
+    let needs = |granted: bool, permission: &str| (!granted).then(|| format!("needs the {} permission", permission));
+    match import.module.as_str() {
+        WASI_P1 if WASI_P1_BASE.contains(&name) => None,
+        WASI_P1 if WASI_P1_FS_READ.contains(&name) => needs(fs_any, "fs:read / fs:write"),
+        WASI_P1 if WASI_P1_FS_WRITE.contains(&name) => needs(!caps.fs_write.is_empty(), "fs:write"),
+        WASI_P1 if name == "clock_time_get" || name == "clock_res_get" => needs(caps.clock, "clock"),
+        WASI_P1 if name == "random_get" => needs(caps.random, "random"),
+        WASI_P1 if name.starts_with("sock_") => needs(caps.net, "net"),
+        WASI_P1 => Some(format!("'{}' is not a {} function", name, WASI_P1)),
+        "nightcore" if name == "log" => needs(caps.log, "log"),
+        "nightcore" if matches!(name, "kv_get" | "kv_put" | "kv_delete") => needs(caps.kv, "kv"),
+        "nightcore" => Some(format!("'{}' is not a nightcore host function", name)),
+        other => Some(format!("module '{}' is not provided by the host", other)),
+    }
+}
+
+fn component_import_denial(import: &ImportEntry, caps: &Capabilities) -> Option<String> {
+    if import.kind != "instance" {
+        return Some(format!("top-level {} imports are not provided by the host", import.kind));
+    }
+    let interface = import.module.split('@').next().unwrap_or_default();
+    let needs = |granted: bool, permission: &str| (!granted).then(|| format!("needs the {} permission", permission));
+    if WASI_P2_BASE.contains(&interface) {
+        None
+    } else if interface.starts_with("wasi:clocks/") {
+        needs(caps.clock, "clock")
+    } else if interface.starts_with("wasi:random/") {
+        needs(caps.random, "random")
+    } else if interface.starts_with("wasi:filesystem/") {
+        needs(!caps.fs_read.is_empty() || !caps.fs_write.is_empty(), "fs:read / fs:write")
+    } else if interface.starts_with("wasi:sockets/") {
+        needs(caps.net, "net")
+    } else {
+        Some(format!("interface '{}' is not provided by the host", interface))
+    }
+}
+
+/// Analyse `bytes` and fail with a full report if any import is disallowed.
+pub fn check_imports(bytes: &[u8], caps: &Capabilities) -> Result<ModuleReport> {
+    let report = analyze(bytes)?;
+    let denied = report.denied_imports(caps);
+    if denied.is_empty() {
+        return Ok(report);
+    }
+    let lines: Vec<String> = denied.iter().map(|d| format!("  - {}: {}", d.import, d.reason)).collect();
+    Err(anyhow!("{} disallowed import(s):\n{}", denied.len(), lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn denied(wat: &str, permissions: &[&str]) -> Vec<String> {
+        let caps = Capabilities::parse(permissions).unwrap();
+        analyze(wat.as_bytes()).unwrap().denied_imports(&caps).into_iter().map(|d| d.reason).collect()
+    }
+
+    const PATH_OPEN: &str = r#"(module (import "wasi_snapshot_preview1" "path_open"
+        (func (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32))))"#;
+
+    #[test]
+    fn path_open_needs_an_fs_permission() {
+        assert_eq!(denied(PATH_OPEN, &["stdout"]), ["needs the fs:read / fs:write permission"]);
+        assert!(denied(PATH_OPEN, &["fs:read:/data"]).is_empty());
+        assert!(denied(PATH_OPEN, &["fs:write:/scratch"]).is_empty());
+    }
+
+    #[test]
+    fn sockets_need_net() {
+        let wat = r#"(module
+            (import "wasi_snapshot_preview1" "sock_accept" (func (param i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "sock_send" (func (param i32 i32 i32 i32 i32) (result i32))))"#;
+        assert_eq!(denied(wat, &["stdout"]), ["needs the net permission", "needs the net permission"]);
+        assert!(denied(wat, &["net"]).is_empty());
+    }
+
+    #[test]
+    fn unknown_modules_are_denied() {
+        let wat = r#"(module (import "env" "syscall" (func)))"#;
+        assert_eq!(denied(wat, &["stdout", "net", "clock"]), ["module 'env' is not provided by the host"]);
+        let wat = r#"(module (import "wasi_snapshot_preview1" "made_up" (func)))"#;
+        assert_eq!(denied(wat, &[]), ["'made_up' is not a wasi_snapshot_preview1 function"]);
+    }
+
+    #[test]
+    fn imported_memory_is_denied() {
+        let wat = r#"(module (import "env" "memory" (memory 1)))"#;
+        assert_eq!(denied(wat, &["stdout"]), ["memory imports are not provided by the host"]);
+    }
+
+    #[test]
+    fn base_wasi_needs_no_permission() {
+        let wat = r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i32))))"#;
+        assert!(denied(wat, &[]).is_empty());
+    }
+
+    #[test]
+    fn precompiled_artifacts_are_rejected() {
+        let err = analyze(b"\x7fELF\x02\x01\x01\0\0\0").unwrap_err();
+        assert_eq!(err.to_string(), "precompiled artifact, not a wasm module");
+    }
+}
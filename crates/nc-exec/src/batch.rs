@@ -0,0 +1,123 @@
+use crate::{ExecOutcome, ExecProof};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+/// Read request payloads for a request/response run. A batch file is JSONL:
+/// one JSON document per non-blank line, fed verbatim to the guest's stdin.
+/// Otherwise the whole file is a single raw payload.
+pub fn read_payloads(path: &Path, batch: bool) -> Result<Vec<Vec<u8>>> {
+    let raw = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if !batch {
+        return Ok(vec![raw]);
+    }
+    let text = String::from_utf8(raw)
+        .with_context(|| format!("{} is not UTF-8 JSONL", path.display()))?;
+    let mut payloads = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        serde_json::from_str::<serde_json::Value>(line)
+            .with_context(|| format!("{}:{}: not a JSON document", path.display(), n + 1))?;
+        payloads.push(line.as_bytes().to_vec());
+    }
+    if payloads.is_empty() {
+        return Err(anyhow!("{} holds no records", path.display()));
+    }
+    Ok(payloads)
+}
+
+/// One line of a batch results file.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRecord {
+    /// Position of the payload in the input (0-based, blank lines skipped).
+    pub index: usize,
+    pub status: String,
+    pub outcome: Option<ExecOutcome>,
+    /// Host-side failure that kept the record from producing a proof.
+    pub error: Option<String>,
+    pub input_sha256: String,
+    pub output_sha256: Option<String>,
+    pub output_truncated: bool,
+    /// Guest stdout: embedded as JSON when it parses, else as text.
+    pub result: Option<serde_json::Value>,
+    pub duration_ms: u64,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub module_sha256: Option<String>,
+    /// Position of this run's entry in the tenant's `proof_history`, when recorded.
+    pub proof_index: Option<usize>,
+}
+
+impl BatchRecord {
+    pub fn new(index: usize, payload: &[u8], run: &Result<ExecProof>, duration_ms: u64) -> Self {
+        let input_sha256 = format!("{:x}", Sha256::digest(payload));
+        match run {
+            Ok(p) => Self {
+                index,
+                status: p.status.clone(),
+                outcome: Some(p.outcome.clone()),
+                error: None,
+                input_sha256,
+                output_sha256: p.stdout_sha256.clone(),
+                output_truncated: p.output_truncated,
+                result: Some(result_value(&p.stdout)),
+                duration_ms,
+                started_at: Some(p.started_at.clone()),
+                finished_at: Some(p.finished_at.clone()),
+                module_sha256: Some(p.module_sha256.clone()),
+                proof_index: None,
+            },
+            Err(e) => Self {
+                index,
+                status: "error".into(),
+                outcome: None,
+                error: Some(format!("{:#}", e)),
+                input_sha256,
+                output_sha256: None,
+                output_truncated: false,
+                result: None,
+                duration_ms,
+                started_at: None,
+                finished_at: None,
+                module_sha256: None,
+                proof_index: None,
+            },
+        }
+    }
+}
+
+fn result_value(stdout: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(stdout)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(stdout).into_owned()))
+}
+
+/// Append-only JSONL sink for batch results; each record is flushed as it
+/// lands so a long batch can be tailed.
+pub struct BatchWriter {
+    out: BufWriter<File>,
+}
+
+impl BatchWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        Ok(Self { out: BufWriter::new(file) })
+    }
+
+    pub fn write(&mut self, record: &BatchRecord) -> Result<()> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -1,6 +1,136 @@
-use anyhow::Result;
-use serde::Serialize;
-use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    convert::TryInto,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+mod analysis;
+mod batch;
+mod manifest;
+mod retry;
+mod schedule;
+pub use analysis::*;
+pub use batch::*;
+pub use manifest::*;
+pub use retry::*;
+pub use schedule::*;
+
+/// How a guest run ended, as classified by the backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExecOutcome {
+    /// Returned from the entrypoint (code 0) or called `proc_exit(code)`.
+    Exited { code: i32 },
+    /// Guest trapped; `trap` is a snake_case trap code such as
+    /// `unreachable`, `memory_out_of_bounds`, `stack_overflow`,
+    /// `interrupt` (wall-clock limit) or `out_of_fuel`.
+    Trapped { trap: String },
+    /// The configured entrypoint is not exported, so nothing ran.
+    MissingEntrypoint { entrypoint: String },
+    /// Failure on the host side (linking, I/O, …) rather than in the guest.
+    HostError { message: String },
+}
+
+impl ExecOutcome {
+    /// Short machine-readable status used in `ExecProof::status`.
+    pub fn status(&self) -> &'static str {
+        match self {
+            ExecOutcome::Exited { code: 0 } => "ok",
+            ExecOutcome::Exited { .. } => "exit_nonzero",
+            ExecOutcome::Trapped { trap } if trap == "interrupt" => "timeout",
+            ExecOutcome::Trapped { trap } if trap == "out_of_fuel" => "out_of_fuel",
+            ExecOutcome::Trapped { .. } => "trap",
+            ExecOutcome::MissingEntrypoint { .. } => "missing_entrypoint",
+            ExecOutcome::HostError { .. } => "host_error",
+        }
+    }
+}
+
+/// Core wasm value type accepted at an invocation boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WasmType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl WasmType {
+    /// Parse a command-line argument as a value of this type.
+    pub fn parse_value(self, raw: &str) -> Result<WasmValue> {
+        let bad = |e: &dyn std::fmt::Display| anyhow!("'{}' is not a valid {}: {}", raw, self, e);
+        Ok(match self {
+            WasmType::I32 => WasmValue::I32(raw.parse().map_err(|e| bad(&e))?),
+            WasmType::I64 => WasmValue::I64(raw.parse().map_err(|e| bad(&e))?),
+            WasmType::F32 => WasmValue::F32(raw.parse().map_err(|e| bad(&e))?),
+            WasmType::F64 => WasmValue::F64(raw.parse().map_err(|e| bad(&e))?),
+        })
+    }
+}
+
+impl std::fmt::Display for WasmType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            WasmType::I32 => "i32",
+            WasmType::I64 => "i64",
+            WasmType::F32 => "f32",
+            WasmType::F64 => "f64",
+        })
+    }
+}
+
+/// A typed argument or result; serialises as `{"i32": 5}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WasmValue {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl WasmValue {
+    pub fn ty(&self) -> WasmType {
+        match self {
+            WasmValue::I32(_) => WasmType::I32,
+            WasmValue::I64(_) => WasmType::I64,
+            WasmValue::F32(_) => WasmType::F32,
+            WasmValue::F64(_) => WasmType::F64,
+        }
+    }
+}
+
+impl std::fmt::Display for WasmValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmValue::I32(v) => write!(f, "{}", v),
+            WasmValue::I64(v) => write!(f, "{}", v),
+            WasmValue::F32(v) => write!(f, "{}", v),
+            WasmValue::F64(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// A named export call recorded in the proof.
+#[derive(Debug, Clone, Serialize)]
+pub struct Invocation {
+    pub export: String,
+    pub args: Vec<WasmValue>,
+    /// Empty when the call did not return (trap, exit, timeout).
+    pub results: Vec<WasmValue>,
+}
 
 #[derive(Debug, Clone, Serialize)]
 pub struct ExecProof {
@@ -10,20 +140,520 @@ pub struct ExecProof {
     pub started_at: String,
     pub finished_at: String,
     pub status: String,
+    pub outcome: ExecOutcome,
     pub backend: String,
+    /// Guest ABI the backend ran: `wasi_p1` (core module) or `wasi_p2`
+    /// (component, WASI 0.2 command world); `None` when not applicable.
+    pub abi: Option<String>,
+    /// Fuel burned by the guest; `None` when the run was not metered.
+    pub fuel_consumed: Option<u64>,
+    /// High-water mark of guest linear memory, when the backend tracks it.
+    pub peak_memory_bytes: Option<u64>,
+    /// SHA-256 of the captured guest stdout / stderr streams.
+    pub stdout_sha256: Option<String>,
+    pub stderr_sha256: Option<String>,
+    /// True when either stream hit its capture cap and was cut short.
+    pub output_truncated: bool,
+    /// Set when the entrypoint was a named export rather than `_start`.
+    pub invocation: Option<Invocation>,
+    /// Profile of a deterministic run; compare outcome, fuel and output
+    /// hashes against a re-execution under it.
+    pub determinism: Option<DeterminismProfile>,
+    /// `nightcore` host module usage; `None` when the guest never called it.
+    pub host_api: Option<HostApiStats>,
+    /// SHA-256 of the payload fed to the guest's stdin, if any.
+    pub stdin_sha256: Option<String>,
+    /// Compiler profile the module was compiled under.
+    pub compiler: Option<CompilerProfile>,
+    /// Hash of every engine setting that shapes compiled code (backend
+    /// version, target, profile); also the artifact-cache key.
+    pub engine_fingerprint: Option<String>,
+    /// Captured guest stdout (within the capture cap). Only its digest goes
+    /// into the serialized proof; request/response runs read the bytes here.
+    #[serde(skip)]
+    pub stdout: Vec<u8>,
 }
 
+#[derive(Clone)]
 pub struct ExecConfig {
     pub tenant: String,
     pub module_path: PathBuf,
-    pub preopen_dirs: Vec<PathBuf>,
+    /// Export called after instantiation; if it is missing the run ends as
+    /// [`ExecOutcome::MissingEntrypoint`]. For components, `_start` means the
+    /// `wasi:cli/run` export.
+    pub entrypoint: String,
+    /// Arguments for the entrypoint, checked against its exported signature.
+    pub call_args: Vec<WasmValue>,
+    /// Request payload fed to the guest's stdin; `None` leaves stdin empty.
+    pub stdin: Option<Vec<u8>>,
+    pub mounts: Vec<Mount>,
     pub env: Vec<(String, String)>,
+    /// Guest argv after the program name (argv[0] is the tenant name).
+    pub args: Vec<String>,
+    /// Expose the host process environment to the guest (before `env`).
+    pub inherit_env: bool,
     pub fuel: Option<u64>,
     pub time_limit_ms: Option<u64>,
+    pub limits: ResourceLimits,
+    pub output: OutputCapture,
+    /// What the guest may touch; anything not granted is absent from its context.
+    pub capabilities: Capabilities,
+    /// Backing store for `nightcore` KV calls; without one (or without the
+    /// `kv` permission) every KV call is denied.
+    pub kv: Option<Arc<dyn KvStore>>,
+    pub kv_limits: KvLimits,
+    /// Run under this profile instead of with host clocks / entropy.
+    pub deterministic: Option<DeterminismProfile>,
+    /// Proposals, stack size and code generator for the tenant's engine.
+    pub compiler: CompilerProfile,
+    /// Set by the orchestrator when the tenant overruns its deadline; the
+    /// backend then stops the guest as if its time limit had passed.
+    pub cancel: CancelToken,
+    /// SHA-256 (hex) that [`SandboxBackend::verify`] returned. When set, the
+    /// backend refuses with [`ModuleChanged`] to run a module that no longer
+    /// hashes to it.
+    pub verified_sha256: Option<String>,
+}
+
+/// The module on disk is not the one that was verified.
+#[derive(Debug)]
+pub struct ModuleChanged {
+    pub verified: String,
+    pub found: String,
+}
+
+impl std::fmt::Display for ModuleChanged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "module changed since it was verified (SHA-256 {} now {})", self.verified, self.found)
+    }
+}
+
+impl std::error::Error for ModuleChanged {}
+
+impl ExecConfig {
+    /// Fail with [`ModuleChanged`] unless `sha256` (hex, any case) is the
+    /// digest `verify` accepted; a config without one is not checked.
+    pub fn check_verified(&self, sha256: &str) -> Result<()> {
+        match &self.verified_sha256 {
+            Some(verified) if !verified.eq_ignore_ascii_case(sha256) => Err(ModuleChanged {
+                verified: verified.to_lowercase(),
+                found: sha256.to_lowercase(),
+            }.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Cooperative cancellation flag shared between an orchestrator and a run.
+/// Clones observe the same flag; the default token is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// One entry of a manifest `"permissions"` list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Permission {
+    Stdout,
+    Stderr,
+    /// `fs:read:<guest path>`
+    FsRead(String),
+    /// `fs:write:<guest path>` (implies read)
+    FsWrite(String),
+    Env,
+    Clock,
+    Random,
+    Net,
+    /// Structured log calls through the `nightcore` host module.
+    Log,
+    /// Tenant-scoped key/value state through the `nightcore` host module.
+    Kv,
+}
+
+impl FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let path_of = |rest: &str| -> Result<String> {
+            if rest.starts_with('/') {
+                Ok(rest.trim_end_matches('/').to_string())
+            } else {
+                Err(anyhow!("permission '{}' needs an absolute guest path", s))
+            }
+        };
+        match s {
+            "stdout" => Ok(Permission::Stdout),
+            "stderr" => Ok(Permission::Stderr),
+            "env" => Ok(Permission::Env),
+            "clock" => Ok(Permission::Clock),
+            "random" => Ok(Permission::Random),
+            "net" => Ok(Permission::Net),
+            "log" => Ok(Permission::Log),
+            "kv" => Ok(Permission::Kv),
+            "fs:read" | "fs:write" => Err(anyhow!("permission '{}' needs a guest path, e.g. {}:/data", s, s)),
+            _ => {
+                if let Some(rest) = s.strip_prefix("fs:read:") {
+                    Ok(Permission::FsRead(path_of(rest)?))
+                } else if let Some(rest) = s.strip_prefix("fs:write:") {
+                    Ok(Permission::FsWrite(path_of(rest)?))
+                } else {
+                    Err(anyhow!(
+                        "unknown permission '{}' (expected stdout, stderr, fs:read:<path>, \
+                         fs:write:<path>, env, clock, random, net, log, kv)",
+                        s
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Capability set handed to a backend. Defaults to nothing granted.
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    pub stdout: bool,
+    pub stderr: bool,
+    pub env: bool,
+    pub clock: bool,
+    pub random: bool,
+    pub net: bool,
+    pub log: bool,
+    pub kv: bool,
+    pub fs_read: Vec<String>,
+    pub fs_write: Vec<String>,
+}
+
+impl Capabilities {
+    /// Parse a manifest permission list; any unknown entry is an error.
+    pub fn parse<S: AsRef<str>>(permissions: &[S]) -> Result<Self> {
+        let perms = permissions
+            .iter()
+            .map(|p| p.as_ref().parse())
+            .collect::<Result<Vec<Permission>>>()?;
+        Ok(Self::from_permissions(&perms))
+    }
+
+    pub fn from_permissions(perms: &[Permission]) -> Self {
+        let mut caps = Capabilities::default();
+        for p in perms {
+            match p {
+                Permission::Stdout => caps.stdout = true,
+                Permission::Stderr => caps.stderr = true,
+                Permission::Env => caps.env = true,
+                Permission::Clock => caps.clock = true,
+                Permission::Random => caps.random = true,
+                Permission::Net => caps.net = true,
+                Permission::Log => caps.log = true,
+                Permission::Kv => caps.kv = true,
+                Permission::FsRead(path) => caps.fs_read.push(path.clone()),
+                Permission::FsWrite(path) => caps.fs_write.push(path.clone()),
+            }
+        }
+        caps
+    }
+
+    /// Grant everything `other` grants as well. Every field is named so a new
+    /// capability cannot be left out of the merge.
+    pub fn merge(&mut self, other: Capabilities) {
+        let Capabilities { stdout, stderr, env, clock, random, net, log, kv, fs_read, fs_write } = other;
+        self.stdout |= stdout;
+        self.stderr |= stderr;
+        self.env |= env;
+        self.clock |= clock;
+        self.random |= random;
+        self.net |= net;
+        self.log |= log;
+        self.kv |= kv;
+        for path in fs_read {
+            if !self.fs_read.contains(&path) {
+                self.fs_read.push(path);
+            }
+        }
+        for path in fs_write {
+            if !self.fs_write.contains(&path) {
+                self.fs_write.push(path);
+            }
+        }
+    }
+
+    /// Effective access to a mount's guest path: `None` when no fs permission
+    /// covers it, `Some(true)` for read-only, `Some(false)` for read-write.
+    pub fn mount_access(&self, guest_path: &str) -> Option<bool> {
+        let guest_path = guest_path.trim_end_matches('/');
+        if self.fs_write.iter().any(|p| p == guest_path) {
+            Some(false)
+        } else if self.fs_read.iter().any(|p| p == guest_path) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// A host directory exposed to the guest under `guest_path`.
+/// Host paths never leak into the guest; only `guest_path` is visible.
+#[derive(Debug, Clone)]
+pub struct Mount {
+    pub host_path: PathBuf,
+    pub guest_path: String,
+    pub read_only: bool,
+}
+
+impl Mount {
+    pub fn read_only(host_path: impl Into<PathBuf>, guest_path: impl Into<String>) -> Self {
+        Self { host_path: host_path.into(), guest_path: guest_path.into(), read_only: true }
+    }
+
+    pub fn read_write(host_path: impl Into<PathBuf>, guest_path: impl Into<String>) -> Self {
+        Self { host_path: host_path.into(), guest_path: guest_path.into(), read_only: false }
+    }
+}
+
+/// Store-level resource caps; `None` leaves the backend default in place.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimits {
+    pub max_memory_mb: Option<u64>,
+    pub max_table_elements: Option<u64>,
+    pub max_instances: Option<u64>,
+}
+
+/// Reproducible-run settings. A deterministic run sees virtual clocks that
+/// start at `clock_start_ms` and advance `clock_step_ns` per read, WASI
+/// randomness seeded from `seed`, canonical NaNs, no threads, no network and
+/// only the declared environment — so re-running the same module on the same
+/// input yields the same outcome, fuel and output hashes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeterminismProfile {
+    #[serde(default)]
+    pub seed: u64,
+    /// Wall-clock reading (ms since the Unix epoch) at the first read.
+    #[serde(default)]
+    pub clock_start_ms: u64,
+    #[serde(default = "default_clock_step_ns")]
+    pub clock_step_ns: u64,
+}
+
+fn default_clock_step_ns() -> u64 {
+    1_000
+}
+
+impl Default for DeterminismProfile {
+    fn default() -> Self {
+        Self { seed: 0, clock_start_ms: 0, clock_step_ns: default_clock_step_ns() }
+    }
+}
+
+/// Code generator behind a tenant's engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompilerStrategy {
+    /// Optimising compiler.
+    #[default]
+    Cranelift,
+    /// Baseline compiler: compiles much faster, runs slower. x86_64 / aarch64 only.
+    Winch,
+}
+
+/// Engine settings a tenant (or the host policy) may pick: WebAssembly
+/// proposals, guest stack size and code generator. `None` keeps the backend
+/// default. Runs under different profiles get separate engines, so their
+/// compiled artifacts never mix.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompilerProfile {
+    pub strategy: Option<CompilerStrategy>,
+    pub simd: Option<bool>,
+    pub relaxed_simd: Option<bool>,
+    pub threads: Option<bool>,
+    pub multi_memory: Option<bool>,
+    pub gc: Option<bool>,
+    /// Guest call-stack budget.
+    pub max_wasm_stack_kb: Option<u32>,
+}
+
+/// Largest `max_wasm_stack_kb` accepted (64 MiB).
+pub const MAX_WASM_STACK_KB: u32 = 64 * 1024;
+
+impl CompilerProfile {
+    /// Field-wise: this profile's settings, `base`'s where this one has none.
+    pub fn or(self, base: CompilerProfile) -> CompilerProfile {
+        CompilerProfile {
+            strategy: self.strategy.or(base.strategy),
+            simd: self.simd.or(base.simd),
+            relaxed_simd: self.relaxed_simd.or(base.relaxed_simd),
+            threads: self.threads.or(base.threads),
+            multi_memory: self.multi_memory.or(base.multi_memory),
+            gc: self.gc.or(base.gc),
+            max_wasm_stack_kb: self.max_wasm_stack_kb.or(base.max_wasm_stack_kb),
+        }
+    }
+
+    /// Combinations no engine can be built for.
+    pub fn problems(&self, deterministic: bool) -> Vec<String> {
+        let mut problems = vec![];
+        match self.max_wasm_stack_kb {
+            Some(0) => problems.push("compiler.max_wasm_stack_kb must be greater than zero".into()),
+            Some(kb) if kb > MAX_WASM_STACK_KB => {
+                problems.push(format!("compiler.max_wasm_stack_kb must be at most {}", MAX_WASM_STACK_KB))
+            }
+            _ => {}
+        }
+        if self.relaxed_simd == Some(true) && self.simd == Some(false) {
+            problems.push("compiler.relaxed_simd needs simd".into());
+        }
+        if self.strategy == Some(CompilerStrategy::Winch) {
+            if self.gc == Some(true) {
+                problems.push("the winch compiler does not support gc".into());
+            }
+            if self.relaxed_simd == Some(true) {
+                problems.push("the winch compiler does not support relaxed_simd".into());
+            }
+        }
+        if deterministic {
+            if self.threads == Some(true) {
+                problems.push("deterministic tenants cannot enable threads".into());
+            }
+            if self.strategy == Some(CompilerStrategy::Winch) {
+                problems.push("deterministic tenants need the cranelift compiler (NaN canonicalisation)".into());
+            }
+        }
+        problems
+    }
+}
+
+/// Durable key/value namespace of one tenant, behind the `nightcore` host
+/// module. Implementations keep it apart from other tenants and from
+/// host-owned records.
+pub trait KvStore: Send + Sync {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()>;
+    /// True when the key existed.
+    fn delete(&self, key: &[u8]) -> Result<bool>;
+    /// Bytes of keys + values currently stored.
+    fn used_bytes(&self) -> Result<u64>;
+}
+
+/// Size caps on guest KV state.
+#[derive(Debug, Clone)]
+pub struct KvLimits {
+    pub max_key_bytes: usize,
+    pub max_value_bytes: usize,
+    /// Keys + values across the tenant's whole namespace.
+    pub max_total_bytes: u64,
+}
+
+impl Default for KvLimits {
+    fn default() -> Self {
+        Self { max_key_bytes: 256, max_value_bytes: 64 * 1024, max_total_bytes: 1024 * 1024 }
+    }
+}
+
+/// `nightcore` host module call counts, kept in the proof for audit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HostApiStats {
+    pub log_entries: u64,
+    /// Log calls past the per-run entry cap.
+    pub log_dropped: u64,
+    pub kv_gets: u64,
+    pub kv_puts: u64,
+    pub kv_deletes: u64,
+    /// Calls refused: no permission, over a size cap, bad arguments.
+    pub rejected: u64,
 }
 
 pub trait SandboxBackend {
     fn name(&self) -> &'static str;
-    fn verify(&self, module_path: &Path) -> Result<()>;
+    /// Check the module's signature, and that its imports fit `capabilities`.
+    /// Returns the lowercase hex SHA-256 of the bytes that were verified.
+    fn verify(&self, module_path: &Path, capabilities: &Capabilities) -> Result<String>;
     fn execute(&self, cfg: &ExecConfig) -> Result<ExecProof>;
 }
+
+/// Async execution on a tokio runtime: guests and backend I/O yield instead of
+/// blocking a thread, so many tenants can share a small runtime. `name` and
+/// `verify` come from [`SandboxBackend`].
+#[async_trait]
+pub trait AsyncSandboxBackend: SandboxBackend + Send + Sync {
+    async fn execute_async(&self, cfg: &ExecConfig) -> Result<ExecProof>;
+}
+
+/// Guest stdio capture: per-stream byte caps and whether to echo the
+/// (sanitised) output on the host terminal after the run.
+#[derive(Debug, Clone)]
+pub struct OutputCapture {
+    pub max_stdout_bytes: usize,
+    pub max_stderr_bytes: usize,
+    pub echo: bool,
+}
+
+impl Default for OutputCapture {
+    fn default() -> Self {
+        Self { max_stdout_bytes: 1024 * 1024, max_stderr_bytes: 1024 * 1024, echo: false }
+    }
+}
+
+/// Step of [`default_verify`] that rejected a module, attached to its error
+/// as context (`err.downcast_ref::<VerifyStage>()`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyStage {
+    /// Key or signature missing, malformed, or not matching the module.
+    Signature,
+    /// Module analysis (unparsable bytes) or a disallowed import.
+    Imports,
+}
+
+impl std::fmt::Display for VerifyStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VerifyStage::Signature => "Signature check failed",
+            VerifyStage::Imports => "Import check failed",
+        })
+    }
+}
+
+/// Shared host-side check: SHA-256 digest + Ed25519 signature against the
+/// `pubkey.b64` / `module.sig` pair sitting next to the module, then the
+/// import analysis against `capabilities`.
+/// Returns the lowercase hex SHA-256 of the bytes that were verified.
+pub fn default_verify(module_path: &Path, capabilities: &Capabilities) -> Result<String> {
+    let dir = module_path.parent().ok_or_else(|| anyhow!("No parent dir"))?;
+    let wasm = fs::read(module_path).context("Read module")?;
+    let digest = Sha256::digest(&wasm);
+    println!("✅ SHA256 digest: {:x}", digest);
+
+    verify_signature(dir, &wasm).context(VerifyStage::Signature)?;
+    println!("✅ Ed25519 signature verified");
+
+    // --- Deny-by-default imports
+    let report = check_imports(&wasm, capabilities).context(VerifyStage::Imports)?;
+    println!("✅ Imports allowed ({} {} import(s))", report.imports.len(), report.kind);
+    Ok(format!("{:x}", digest))
+}
+
+/// Ed25519 signature verification of `wasm` against the tenant's key pair.
+fn verify_signature(dir: &Path, wasm: &[u8]) -> Result<()> {
+    let pk_b64 = fs::read_to_string(dir.join("pubkey.b64")).context("pubkey.b64")?;
+    let sig_b64 = fs::read_to_string(dir.join("module.sig")).context("module.sig")?;
+    let sig_bytes = STANDARD.decode(sig_b64.trim()).context("Signature not valid base64")?;
+
+    let pk_vec = STANDARD.decode(pk_b64.trim()).context("Invalid base64 pubkey")?;
+    let pk: [u8; 32] = pk_vec.try_into().map_err(|_| anyhow!("Pubkey len != 32"))?;
+    let sig_arr: [u8; 64] = sig_bytes.try_into().map_err(|_| anyhow!("Signature len != 64"))?;
+
+    let vk = VerifyingKey::from_bytes(&pk).context("Bad pubkey")?;
+    let sig = Signature::from_bytes(&sig_arr);
+    vk.verify(wasm, &sig).context("Signature verify failed")
+}
@@ -0,0 +1,349 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    CancelToken, Capabilities, CompilerProfile, DeterminismProfile, ExecConfig, FailureClass, KvLimits, Mount,
+    OutputCapture, Permission, ResourceLimits, RetryPolicy, Schedule, WasmType, WasmValue,
+};
+
+/// Newest `manifest.json` schema this crate understands.
+/// Schema 0 is the pre-typed layout with limits (`fuel_limit`, `timeout_ms`,
+/// `max_memory_mb`, …) at the top level.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+/// Backends a manifest may pin itself to.
+pub const KNOWN_BACKENDS: &[&str] = &["wasmtime", "firecracker"];
+
+/// Typed `modules/<tenant>/manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TenantManifest {
+    #[serde(default)]
+    pub schema: u32,
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Export invoked after instantiation.
+    #[serde(default = "default_entrypoint")]
+    pub entrypoint: String,
+    /// Pin the tenant to a backend; `None` runs on whatever the host selects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Exports callable by name (`nightcore invoke`), with their signatures.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exports: Vec<ManifestExport>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub limits: ManifestLimits,
+    #[serde(default)]
+    pub mounts: Vec<ManifestMount>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub inherit_env: bool,
+    /// Run under a deterministic profile (`{}` for the defaults).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deterministic: Option<DeterminismProfile>,
+    /// Engine proposals / stack / code generator; unset fields fall back to
+    /// the host policy's `[compiler]` section.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerProfile>,
+    /// Retry settings for failed runs; unset fields fall back to the host
+    /// policy's `[retry]` section.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<ManifestRetry>,
+    /// When `nightcore serve` runs the tenant; the policy's `[serve]`
+    /// section can override it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    /// Key-seal hash maintained by the keyseal tool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey_hash: Option<String>,
+
+    // Schema 0 top-level limits, folded into `limits` on load.
+    #[serde(default, skip_serializing)]
+    fuel_limit: Option<u64>,
+    #[serde(default, skip_serializing)]
+    timeout_ms: Option<u64>,
+    #[serde(default, skip_serializing)]
+    max_memory_mb: Option<u64>,
+    #[serde(default, skip_serializing)]
+    max_table_elements: Option<u64>,
+    #[serde(default, skip_serializing)]
+    max_instances: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestLimits {
+    pub fuel: Option<u64>,
+    pub timeout_ms: Option<u64>,
+    pub max_memory_mb: Option<u64>,
+    pub max_table_elements: Option<u64>,
+    pub max_instances: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestExport {
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<WasmType>,
+    #[serde(default)]
+    pub results: Vec<WasmType>,
+}
+
+impl std::fmt::Display for ManifestExport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |tys: &[WasmType]| tys.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ");
+        write!(f, "{}({}) -> ({})", self.name, list(&self.params), list(&self.results))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRetry {
+    pub max_attempts: Option<u32>,
+    pub backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+    pub retry_on: Option<Vec<FailureClass>>,
+}
+
+impl ManifestRetry {
+    /// `base` with this manifest's settings applied on top.
+    pub fn over(&self, base: &RetryPolicy) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.unwrap_or(base.max_attempts),
+            backoff_ms: self.backoff_ms.unwrap_or(base.backoff_ms),
+            max_backoff_ms: self.max_backoff_ms.unwrap_or(base.max_backoff_ms),
+            retry_on: self.retry_on.clone().unwrap_or_else(|| base.retry_on.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestMount {
+    pub host: PathBuf,
+    pub guest: String,
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+}
+
+fn default_entrypoint() -> String {
+    "_start".into()
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+impl TenantManifest {
+    /// Load, normalise and validate `<dir>/manifest.json`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join("manifest.json");
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&raw).with_context(|| format!("invalid manifest {}", path.display()))
+    }
+
+    /// Parse and validate manifest JSON (schema 0 or 1).
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut manifest: TenantManifest = serde_json::from_str(raw)?;
+        manifest.validate()?;
+        if manifest.schema == 0 {
+            manifest.limits = manifest.effective_limits();
+            manifest.fuel_limit = None;
+            manifest.timeout_ms = None;
+            manifest.max_memory_mb = None;
+            manifest.max_table_elements = None;
+            manifest.max_instances = None;
+        }
+        Ok(manifest)
+    }
+
+    /// `limits`, with schema 0 top-level values filling the gaps.
+    fn effective_limits(&self) -> ManifestLimits {
+        ManifestLimits {
+            fuel: self.limits.fuel.or(self.fuel_limit),
+            timeout_ms: self.limits.timeout_ms.or(self.timeout_ms),
+            max_memory_mb: self.limits.max_memory_mb.or(self.max_memory_mb),
+            max_table_elements: self.limits.max_table_elements.or(self.max_table_elements),
+            max_instances: self.limits.max_instances.or(self.max_instances),
+        }
+    }
+
+    /// Check every field and report all problems at once.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems: Vec<String> = vec![];
+
+        if self.schema > MANIFEST_SCHEMA_VERSION {
+            problems.push(format!(
+                "schema {} is newer than supported ({})", self.schema, MANIFEST_SCHEMA_VERSION
+            ));
+        }
+        let legacy = ManifestLimits {
+            fuel: self.fuel_limit,
+            timeout_ms: self.timeout_ms,
+            max_memory_mb: self.max_memory_mb,
+            max_table_elements: self.max_table_elements,
+            max_instances: self.max_instances,
+        };
+        if self.schema >= 1 && legacy != ManifestLimits::default() {
+            problems.push("top-level limits (fuel_limit, timeout_ms, max_*) moved under \"limits\" in schema 1".into());
+        }
+        if self.name.is_empty()
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            problems.push(format!("name '{}' must be non-empty [A-Za-z0-9._-]", self.name));
+        }
+        let semver = self.version.split('.').collect::<Vec<_>>();
+        if semver.len() != 3 || semver.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+            problems.push(format!("version '{}' is not MAJOR.MINOR.PATCH", self.version));
+        }
+        if self.entrypoint.is_empty() {
+            problems.push("entrypoint must not be empty".into());
+        }
+        for (i, export) in self.exports.iter().enumerate() {
+            if export.name.is_empty() {
+                problems.push("exports entry with an empty name".into());
+            } else if self.exports[..i].iter().any(|e| e.name == export.name) {
+                problems.push(format!("export '{}' is declared twice", export.name));
+            }
+        }
+        if let Some(export) = self.export(&self.entrypoint) {
+            if !export.params.is_empty() {
+                problems.push(format!("entrypoint '{}' takes parameters; call it with `nightcore invoke`", export.name));
+            }
+        }
+        if let Some(backend) = &self.backend {
+            if !KNOWN_BACKENDS.contains(&backend.as_str()) {
+                problems.push(format!("unknown backend '{}' (expected {})", backend, KNOWN_BACKENDS.join(" | ")));
+            }
+        }
+
+        let mut perms = vec![];
+        for p in &self.permissions {
+            match p.parse::<Permission>() {
+                Ok(perm) => perms.push(perm),
+                Err(e) => problems.push(e.to_string()),
+            }
+        }
+        let caps = Capabilities::from_permissions(&perms);
+
+        let limits = self.effective_limits();
+        for (field, value) in [
+            ("fuel", limits.fuel),
+            ("timeout_ms", limits.timeout_ms),
+            ("max_memory_mb", limits.max_memory_mb),
+            ("max_table_elements", limits.max_table_elements),
+            ("max_instances", limits.max_instances),
+        ] {
+            if value == Some(0) {
+                problems.push(format!("limits.{} must be greater than zero", field));
+            }
+        }
+
+        for m in &self.mounts {
+            if !m.guest.starts_with('/') {
+                problems.push(format!("mount guest path '{}' must be absolute", m.guest));
+            } else if caps.mount_access(&m.guest).is_none() {
+                problems.push(format!("mount {} has no matching fs:read / fs:write permission", m.guest));
+            }
+        }
+        for key in self.env.keys() {
+            if key.is_empty() || key.contains('=') {
+                problems.push(format!("env key '{}' is invalid", key));
+            }
+        }
+        if let Some(compiler) = &self.compiler {
+            problems.extend(compiler.problems(self.deterministic.is_some()));
+        }
+        if let Some(retry) = &self.retry {
+            problems.extend(retry.over(&RetryPolicy::default()).problems());
+        }
+        if self.deterministic.is_some() {
+            if caps.net {
+                problems.push("deterministic tenants cannot hold the net permission".into());
+            }
+            if self.inherit_env {
+                problems.push("deterministic tenants cannot inherit the host environment".into());
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("{} problem(s):\n  - {}", problems.len(), problems.join("\n  - ")))
+        }
+    }
+
+    pub fn export(&self, name: &str) -> Option<&ManifestExport> {
+        self.exports.iter().find(|e| e.name == name)
+    }
+
+    /// Parse command-line arguments for a declared export against its
+    /// parameter types. Undeclared exports cannot be invoked.
+    pub fn invocation_args(&self, export: &str, raw: &[String]) -> Result<Vec<WasmValue>> {
+        let decl = self.export(export).ok_or_else(|| {
+            let declared: Vec<&str> = self.exports.iter().map(|e| e.name.as_str()).collect();
+            anyhow!("'{}' is not a declared export of {} (declared: {})",
+                export, self.name, if declared.is_empty() { "none".into() } else { declared.join(", ") })
+        })?;
+        if raw.len() != decl.params.len() {
+            return Err(anyhow!("{} takes {} argument(s), got {}", decl, decl.params.len(), raw.len()));
+        }
+        decl.params.iter().zip(raw).map(|(ty, arg)| ty.parse_value(arg)).collect()
+    }
+
+    /// Declared permissions; setting `env` variables implies the `env` grant.
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        let mut caps = Capabilities::parse(&self.permissions)?;
+        caps.env |= !self.env.is_empty();
+        Ok(caps)
+    }
+
+    /// Sandbox config described by the manifest alone; hosts layer their
+    /// policy (limit fallbacks, output caps, permission switches) on top.
+    pub fn exec_config(&self, tenant: &str, module_path: &Path) -> Result<ExecConfig> {
+        Ok(ExecConfig {
+            tenant: tenant.to_string(),
+            module_path: module_path.to_path_buf(),
+            entrypoint: self.entrypoint.clone(),
+            call_args: vec![],
+            stdin: None,
+            mounts: self.mounts.iter().map(|m| Mount {
+                host_path: m.host.clone(),
+                guest_path: m.guest.clone(),
+                read_only: m.read_only,
+            }).collect(),
+            env: self.env.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            args: self.args.clone(),
+            inherit_env: self.inherit_env,
+            fuel: self.limits.fuel,
+            time_limit_ms: self.limits.timeout_ms,
+            limits: ResourceLimits {
+                max_memory_mb: self.limits.max_memory_mb,
+                max_table_elements: self.limits.max_table_elements,
+                max_instances: self.limits.max_instances,
+            },
+            output: OutputCapture::default(),
+            capabilities: self.capabilities()?,
+            kv: None,
+            kv_limits: KvLimits::default(),
+            deterministic: self.deterministic.clone(),
+            compiler: self.compiler.unwrap_or_default(),
+            cancel: CancelToken::default(),
+            verified_sha256: None,
+        })
+    }
+}
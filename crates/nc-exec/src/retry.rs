@@ -0,0 +1,166 @@
+use crate::ExecOutcome;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Stage or outcome a tenant run failed with, as used by retry policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureClass {
+    /// Host-side I/O before the run: tenant state (e.g. a locked DB), module file.
+    Io,
+    /// Manifest or call could not be turned into a run config.
+    Config,
+    /// Signature or import check failed. Never retried.
+    Verification,
+    /// The backend failed to run the guest (engine, VM boot, linking).
+    Backend,
+    /// Guest time limit or orchestration deadline.
+    Timeout,
+    OutOfFuel,
+    Trap,
+    /// Guest exited with a non-zero code.
+    Exit,
+}
+
+impl FailureClass {
+    /// Class of a completed run's outcome; `None` for success.
+    pub fn of_outcome(outcome: &ExecOutcome) -> Option<FailureClass> {
+        match outcome {
+            ExecOutcome::Exited { code: 0 } => None,
+            ExecOutcome::Exited { .. } => Some(FailureClass::Exit),
+            ExecOutcome::Trapped { trap } if trap == "interrupt" => Some(FailureClass::Timeout),
+            ExecOutcome::Trapped { trap } if trap == "out_of_fuel" => Some(FailureClass::OutOfFuel),
+            ExecOutcome::Trapped { .. } => Some(FailureClass::Trap),
+            ExecOutcome::MissingEntrypoint { .. } => Some(FailureClass::Config),
+            ExecOutcome::HostError { .. } => Some(FailureClass::Backend),
+        }
+    }
+}
+
+impl std::fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            FailureClass::Io => "io",
+            FailureClass::Config => "config",
+            FailureClass::Verification => "verification",
+            FailureClass::Backend => "backend",
+            FailureClass::Timeout => "timeout",
+            FailureClass::OutOfFuel => "out_of_fuel",
+            FailureClass::Trap => "trap",
+            FailureClass::Exit => "exit",
+        })
+    }
+}
+
+/// When to run a failed tenant again. Backoff doubles per attempt, capped at
+/// `max_backoff_ms`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 1 disables retries.
+    pub max_attempts: u32,
+    /// Delay before the second attempt.
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Failure classes worth another attempt.
+    pub retry_on: Vec<FailureClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_ms: 200,
+            max_backoff_ms: 5_000,
+            retry_on: vec![FailureClass::Io, FailureClass::Backend],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.max_attempts == 0 {
+            problems.push("retry.max_attempts must be at least 1".into());
+        }
+        if self.retry_on.contains(&FailureClass::Verification) {
+            problems.push("retry.retry_on cannot include verification; signature failures are never retried".into());
+        }
+        problems
+    }
+
+    /// Delay before the next attempt after attempt `attempt` (1-based) failed
+    /// with `class`; `None` when it should not be retried.
+    pub fn retry_after(&self, class: FailureClass, attempt: u32) -> Option<Duration> {
+        if class == FailureClass::Verification || attempt >= self.max_attempts || !self.retry_on.contains(&class) {
+            return None;
+        }
+        let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+        Some(Duration::from_millis(self.backoff_ms.saturating_mul(factor).min(self.max_backoff_ms)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, backoff_ms: 100, max_backoff_ms: 1_000, ..RetryPolicy::default() }
+    }
+
+    #[test]
+    fn backoff_doubles_per_attempt_up_to_the_cap() {
+        let p = policy(10);
+        let delays: Vec<u64> = (1..=6)
+            .map(|n| p.retry_after(FailureClass::Io, n).unwrap().as_millis() as u64)
+            .collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1_000, 1_000]);
+    }
+
+    #[test]
+    fn backoff_saturates_on_late_attempts() {
+        let p = RetryPolicy { max_attempts: u32::MAX, max_backoff_ms: u64::MAX, ..policy(0) };
+        assert_eq!(p.retry_after(FailureClass::Io, 200), Some(Duration::from_millis(u64::MAX)));
+    }
+
+    #[test]
+    fn stops_at_max_attempts() {
+        let p = policy(3);
+        assert!(p.retry_after(FailureClass::Backend, 1).is_some());
+        assert!(p.retry_after(FailureClass::Backend, 2).is_some());
+        assert_eq!(p.retry_after(FailureClass::Backend, 3), None);
+        assert_eq!(policy(1).retry_after(FailureClass::Backend, 1), None);
+    }
+
+    #[test]
+    fn only_listed_classes_are_retried() {
+        let p = policy(3);
+        assert_eq!(p.retry_after(FailureClass::Config, 1), None);
+        assert_eq!(p.retry_after(FailureClass::Trap, 1), None);
+        let p = RetryPolicy { retry_on: vec![FailureClass::Trap], ..p };
+        assert!(p.retry_after(FailureClass::Trap, 1).is_some());
+        assert_eq!(p.retry_after(FailureClass::Io, 1), None);
+    }
+
+    #[test]
+    fn verification_is_never_retried() {
+        let p = RetryPolicy { retry_on: vec![FailureClass::Verification], ..policy(3) };
+        assert_eq!(p.retry_after(FailureClass::Verification, 1), None);
+        assert_eq!(p.problems().len(), 1);
+        assert!(policy(3).problems().is_empty());
+        assert_eq!(policy(0).problems().len(), 1);
+    }
+
+    #[test]
+    fn outcomes_map_to_classes() {
+        assert_eq!(FailureClass::of_outcome(&ExecOutcome::Exited { code: 0 }), None);
+        assert_eq!(FailureClass::of_outcome(&ExecOutcome::Exited { code: 3 }), Some(FailureClass::Exit));
+        assert_eq!(FailureClass::of_outcome(&ExecOutcome::Trapped { trap: "interrupt".into() }), Some(FailureClass::Timeout));
+        assert_eq!(FailureClass::of_outcome(&ExecOutcome::Trapped { trap: "out_of_fuel".into() }), Some(FailureClass::OutOfFuel));
+        assert_eq!(FailureClass::of_outcome(&ExecOutcome::Trapped { trap: "unreachable".into() }), Some(FailureClass::Trap));
+        assert_eq!(
+            FailureClass::of_outcome(&ExecOutcome::MissingEntrypoint { entrypoint: "_start".into() }),
+            Some(FailureClass::Config)
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, str::FromStr, time::Duration};
+
+/// When a tenant runs under the daemon: `@every <n><s|m|h|d>`, or a cron
+/// expression — 5 fields (minute first, classic day of week 0–7 with Sunday
+/// as 0 or 7), 6/7 fields (with seconds / year, in the `cron` crate's syntax:
+/// day of week 1–7 from Sunday) or `@hourly`, `@daily`, `@weekly`,
+/// `@monthly`, `@yearly`. Times are UTC.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+    source: String,
+    kind: ScheduleKind,
+}
+
+#[derive(Debug, Clone)]
+enum ScheduleKind {
+    Every(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// First run strictly after `after`; `None` if the expression has no
+    /// further occurrences.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.kind {
+            ScheduleKind::Every(every) => Some(after + chrono::Duration::from_std(*every).ok()?),
+            ScheduleKind::Cron(cron) => cron.after(&after).next(),
+        }
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.trim().to_string();
+        let kind = if let Some(every) = source.strip_prefix("@every") {
+            ScheduleKind::Every(parse_interval(every.trim())?)
+        } else {
+            let fields: Vec<&str> = source.split_whitespace().collect();
+            // The cron crate wants a seconds field and counts weekdays from
+            // Sunday = 1; a classic 5-field expression fires at second 0.
+            let expr = match fields[..] {
+                [minute, hour, dom, month, dow] => {
+                    let dow = classic_day_of_week(dow).map_err(|e| anyhow!("invalid schedule '{}': {}", source, e))?;
+                    format!("0 {} {} {} {} {}", minute, hour, dom, month, dow)
+                }
+                _ => source.clone(),
+            };
+            let cron = cron::Schedule::from_str(&expr).map_err(|e| anyhow!("invalid schedule '{}': {}", source, e))?;
+            ScheduleKind::Cron(Box::new(cron))
+        };
+        Ok(Self { source, kind })
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(s: Schedule) -> String {
+        s.source
+    }
+}
+
+impl std::fmt::Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Rewrite a classic day-of-week field (0–7, Sunday = 0 or 7) into the cron
+/// crate's numbering (1–7, Sunday = 1). Names (`MON-FRI`) pass through.
+/// Stepped items are expanded into the days they select first: shifting
+/// their bounds instead would move or drop days (`1/2` includes Sunday as 7).
+fn classic_day_of_week(field: &str) -> Result<String> {
+    let day = |s: &str| -> Result<Option<u32>> {
+        match s.parse::<u32>() {
+            Ok(n @ 0..=7) => Ok(Some(n)),
+            Ok(n) => Err(anyhow!("day of week {} is out of range (0-7)", n)),
+            Err(_) => Ok(None),
+        }
+    };
+    let mut items = vec![];
+    for item in field.split(',') {
+        if let Some((base, step)) = item.split_once('/') {
+            let step = step.parse::<usize>().ok().filter(|&s| s > 0)
+                .ok_or_else(|| anyhow!("day-of-week step in '{}' must be a positive number", item))?;
+            let bounds = match base.split_once('-') {
+                _ if base == "*" => Some((0, 6)),
+                Some((from, to)) => day(from)?.zip(day(to)?),
+                None => day(base)?.map(|from| (from, 7)),
+            };
+            let Some((from, to)) = bounds else {
+                items.push(item.to_string());
+                continue;
+            };
+            let days = (from..=to).step_by(step).map(|n| n % 7 + 1).collect::<BTreeSet<_>>();
+            if days.is_empty() {
+                return Err(anyhow!("day-of-week range '{}' selects no days", item));
+            }
+            items.extend(days.iter().map(u32::to_string));
+            continue;
+        }
+        match item.split_once('-') {
+            Some((from, to)) => match (day(from)?, day(to)?) {
+                // A range up to 7 runs through Saturday to Sunday, which is
+                // the start of the cron crate's week.
+                (Some(0), Some(7)) => items.push("1-7".into()),
+                (Some(7), Some(7)) => items.push("1".into()),
+                (Some(from), Some(7)) => {
+                    items.push(format!("{}-7", from + 1));
+                    items.push("1".into());
+                }
+                (Some(from), Some(to)) => items.push(format!("{}-{}", from % 7 + 1, to + 1)),
+                _ => items.push(item.to_string()),
+            },
+            None => match day(item)? {
+                Some(n) => items.push((n % 7 + 1).to_string()),
+                None => items.push(item.to_string()),
+            },
+        }
+    }
+    Ok(items.join(","))
+}
+
+/// `30s`, `5m`, `2h`, `1d`.
+fn parse_interval(s: &str) -> Result<Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: u64 = n.parse().map_err(|_| anyhow!("invalid interval '{}' (expected e.g. 30s, 5m, 2h, 1d)", s))?;
+    let secs = match unit {
+        "s" => n,
+        "m" => n.saturating_mul(60),
+        "h" => n.saturating_mul(3600),
+        "d" => n.saturating_mul(86_400),
+        _ => return Err(anyhow!("invalid interval unit in '{}' (expected s, m, h or d)", s)),
+    };
+    if secs == 0 {
+        return Err(anyhow!("interval must be at least 1s"));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Datelike, TimeZone, Weekday};
+
+    /// Friday 2026-10-16, 12:00 UTC.
+    fn friday_noon() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()
+    }
+
+    /// Weekdays of the next `n` runs after `after`.
+    fn weekdays(expr: &str, after: DateTime<Utc>, n: usize) -> Vec<Weekday> {
+        let schedule: Schedule = expr.parse().unwrap();
+        let mut at = after;
+        (0..n)
+            .map(|_| {
+                at = schedule.next_after(at).unwrap();
+                at.weekday()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classic_weekdays_count_from_sunday_zero() {
+        use Weekday::*;
+        assert_eq!(weekdays("0 9 * * 1-5", friday_noon(), 5), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(weekdays("0 9 * * 0", friday_noon(), 2), [Sun, Sun]);
+        assert_eq!(weekdays("0 9 * * 7", friday_noon(), 1), [Sun]);
+        assert_eq!(weekdays("0 9 * * 0,6", friday_noon(), 3), [Sat, Sun, Sat]);
+        assert_eq!(weekdays("0 9 * * 5-7", friday_noon(), 4), [Sat, Sun, Fri, Sat]);
+        assert_eq!(weekdays("0 9 * * 0-7/2", friday_noon(), 4), [Sat, Sun, Tue, Thu]);
+        assert_eq!(weekdays("0 9 * * MON-FRI", friday_noon(), 2), [Mon, Tue]);
+        assert!("* * * * 0".parse::<Schedule>().is_ok());
+    }
+
+    #[test]
+    fn day_of_week_field_rewrite() {
+        assert_eq!(classic_day_of_week("1-5").unwrap(), "2-6");
+        assert_eq!(classic_day_of_week("0").unwrap(), "1");
+        assert_eq!(classic_day_of_week("6,7").unwrap(), "7,1");
+        assert_eq!(classic_day_of_week("5-7").unwrap(), "6-7,1");
+        assert_eq!(classic_day_of_week("*/2").unwrap(), "1,3,5,7");
+        assert_eq!(classic_day_of_week("1-5/2").unwrap(), "2,4,6");
+        assert_eq!(classic_day_of_week("3-7/2").unwrap(), "1,4,6");
+        assert_eq!(classic_day_of_week("MON-FRI/2").unwrap(), "MON-FRI/2");
+        assert!(classic_day_of_week("8").is_err());
+        assert!(classic_day_of_week("1/0").is_err());
+        assert!(classic_day_of_week("5-1/2").is_err());
+    }
+
+    #[test]
+    fn stepped_day_of_week_keeps_sunday() {
+        use Weekday::*;
+        // Monday, Wednesday, Friday and Sunday (as 7).
+        assert_eq!(classic_day_of_week("1/2").unwrap(), "1,2,4,6");
+        assert_eq!(weekdays("0 9 * * 1/2", friday_noon(), 4), [Sun, Mon, Wed, Fri]);
+    }
+
+    #[test]
+    fn six_field_expressions_keep_the_cron_crate_numbering() {
+        // Seconds first; day of week 2 is Monday in the cron crate.
+        assert_eq!(weekdays("0 0 9 * * 2", friday_noon(), 1), [Weekday::Mon]);
+    }
+
+    #[test]
+    fn every_adds_the_interval() {
+        let schedule: Schedule = "@every 90s".parse().unwrap();
+        assert_eq!(schedule.next_after(friday_noon()), Some(friday_noon() + chrono::Duration::seconds(90)));
+        assert_eq!(schedule.as_str(), "@every 90s");
+        assert!("@every".parse::<Schedule>().is_err());
+        assert!("@every 0m".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn interval_units_and_edge_cases() {
+        assert_eq!(parse_interval("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_interval("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_interval("2h").unwrap(), Duration::from_secs(7_200));
+        assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(86_400));
+        assert!(parse_interval("0s").is_err());
+        assert!(parse_interval("10w").is_err());
+        assert!(parse_interval("10").is_err());
+        assert!(parse_interval("s").is_err());
+        assert!(parse_interval("-5s").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_cron() {
+        assert!("* * *".parse::<Schedule>().is_err());
+        assert!("61 * * * *".parse::<Schedule>().is_err());
+        assert!("0 9 * * 8".parse::<Schedule>().is_err());
+    }
+}
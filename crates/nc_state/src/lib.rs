@@ -1,7 +1,10 @@
 use anyhow::{Context, Result};
+use nc_exec::KvStore;
 use serde::{Serialize, de::DeserializeOwned};
 use std::path::Path;
 
+/// Cheap to clone: clones share the same open database.
+#[derive(Clone)]
 pub struct TenantState {
     db: sled::Db,
 }
@@ -30,12 +33,14 @@ impl TenantState {
         }
     }
 
-    pub fn append_json<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+    /// Append to the JSON array under `key`; returns the new entry's index.
+    pub fn append_json<T: Serialize>(&self, key: &str, value: &T) -> Result<usize> {
         let mut arr: Vec<serde_json::Value> = self
             .get_json::<Vec<serde_json::Value>>(key)?
             .unwrap_or_default();
         arr.push(serde_json::to_value(value)?);
-        self.put_json(key, &arr)
+        self.put_json(key, &arr)?;
+        Ok(arr.len() - 1)
     }
 
     /// ✅ NEW: List all JSON entries under a given key (returns empty Vec if none)
@@ -43,3 +48,36 @@ impl TenantState {
         Ok(self.get_json::<Vec<T>>(key)?.unwrap_or_default())
     }
 }
+
+/// Guest key/value state lives in its own tree, so a tenant can never read or
+/// overwrite host records (`last_proof`, `proof_history`, …) kept beside it.
+const GUEST_KV_TREE: &str = "guest_kv";
+
+impl KvStore for TenantState {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.open_tree(GUEST_KV_TREE)?.get(key)?.map(|v| v.to_vec()))
+    }
+
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<()> {
+        let tree = self.db.open_tree(GUEST_KV_TREE)?;
+        tree.insert(key, value)?;
+        tree.flush()?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &[u8]) -> Result<bool> {
+        let tree = self.db.open_tree(GUEST_KV_TREE)?;
+        let existed = tree.remove(key)?.is_some();
+        tree.flush()?;
+        Ok(existed)
+    }
+
+    fn used_bytes(&self) -> Result<u64> {
+        let mut total = 0;
+        for entry in self.db.open_tree(GUEST_KV_TREE)?.iter() {
+            let (k, v) = entry?;
+            total += (k.len() + v.len()) as u64;
+        }
+        Ok(total)
+    }
+}
@@ -167,7 +167,7 @@ pub fn verify_upgrade(manifest_path: &Path) -> Result<()> {
         );
 
         for key_path in &key_files {
-            let key_raw = fs::read_to_string(&key_path).context("Reading maintainer public key")?;
+            let key_raw = fs::read_to_string(key_path).context("Reading maintainer public key")?;
             let key_bytes = STANDARD.decode(key_raw.trim()).context("Invalid base64 pubkey")?;
             if key_bytes.len() != 32 {
                 continue;
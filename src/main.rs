@@ -1,9 +1,9 @@
 #![allow(static_mut_refs)]
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
 use std::{
-    fs, path::PathBuf, thread, sync::mpsc,
+    fs, path::PathBuf, sync::Arc,
     time::{Duration, Instant},
 };
 use base64::{engine::general_purpose::STANDARD, Engine as _};
@@ -11,13 +11,21 @@ use sha2::{Sha256, Digest};
 use serde::{Serialize, Deserialize};
 use serde_json::Value;
 use chrono::{Utc, DateTime};
-use open; // ✅ for automatic browser open
+use nc_exec::{CancelToken, ExecOutcome, FailureClass, SandboxBackend};
+use nc_exec_wasmtime::{EngineOptions, WasmtimeBackend};
 
 mod verify;
 mod aufs;
 mod sign_tenant;
 mod unlock;
-mod firecracker_adapter;
+mod policy;
+mod scheduler;
+#[cfg(target_os = "linux")]
+mod audit;
+#[cfg(target_os = "linux")]
+mod watch;
+#[cfg(unix)]
+mod serve;
 
 
 #[derive(Parser)]
@@ -47,9 +55,17 @@ enum Commands {
         /// Optional path to a single tenant module
         path: Option<PathBuf>,
 
-        /// 🕒 Optional Firecracker VM timeout (seconds)
-        #[arg(long, default_value_t = 5)]
-        vm_timeout: u64, // ✅ Added safely
+        /// 📨 Feed this file to the tenant's stdin as a single request
+        #[arg(long, conflicts_with = "batch")]
+        input: Option<PathBuf>,
+
+        /// 📦 Run once per JSONL record, feeding each line to stdin
+        #[arg(long)]
+        batch: Option<PathBuf>,
+
+        /// Results JSONL for --input / --batch (default logs/<tenant>/results.jsonl)
+        #[arg(long)]
+        results: Option<PathBuf>,
     },
 
     VerifyEnv,
@@ -66,6 +82,70 @@ enum Commands {
         dir: PathBuf,
     },
 
+    /// 🔁 Re-run a deterministic tenant and compare with its recorded proof
+    Replay {
+        path: PathBuf,
+
+        /// proof_report.jsonl from another node (default: this node's last_proof)
+        #[arg(long)]
+        against: Option<PathBuf>,
+
+        #[arg(long, default_value = "wasmtime")]
+        backend: String,
+    },
+
+    /// 🎯 Call a manifest-declared export with typed arguments (after `--`)
+    Invoke {
+        #[arg(long)]
+        tenant: String,
+
+        #[arg(long = "fn")]
+        export: String,
+
+        #[arg(long, default_value = "wasmtime")]
+        backend: String,
+
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// 🛰️ Run tenants on their schedules until stopped (see `nightcore status`)
+    #[cfg(unix)]
+    Serve {
+        #[arg(long, default_value = "wasmtime")]
+        backend: String,
+
+        #[arg(long, default_value_t = 0)]
+        parallel: usize,
+
+        /// Status socket (default: policy [serve] socket)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+
+    /// 📡 Show schedules and last runs of a running `nightcore serve`
+    #[cfg(unix)]
+    Status {
+        /// Status socket (default: policy [serve] socket)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+
+        /// Print the daemon's raw JSON reply
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// 👁️ Re-verify tenants as soon as their files under modules/ change
+    #[cfg(target_os = "linux")]
+    Watch {
+        /// Run each tenant that re-verifies (default: policy [watch] run_on_change)
+        #[arg(long)]
+        run: bool,
+
+        #[arg(long, default_value = "wasmtime")]
+        backend: String,
+    },
+
     ExportPubkeyHashes,
 
     /// Build a historical HTML ledger from /state (add --diff for per-tenant deltas)
@@ -104,22 +184,53 @@ enum Commands {
 
 /// ─────────────────────────────────────────────────────────────
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Default)]
 struct OrchestrationReport {
     timestamp: String,
     tenants_executed: usize,
+    /// Wall-clock time of the whole run.
     total_time_s: f64,
     avg_time_s: f64,
+    /// Sum of per-tenant durations; over `total_time_s` it gives the speed-up.
+    busy_time_s: f64,
+    /// Tenants with status `timeout`: stopped by their guest time limit or
+    /// cancelled at their orchestration deadline.
+    timeouts: usize,
+    /// Attempts beyond each tenant's first.
+    retries: usize,
     parallel_threads: usize,
     tenants: Vec<TenantTiming>,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 struct TenantTiming {
     name: String,
     sha: String,
     duration_s: f64,
+    /// `ok`, `exit N`, `timeout`, `out_of_fuel`, `trap: …` or `error: …`.
     status: String,
+    backend: String,
+    exec_duration_s: f64,
+    /// Worker slot that ran the tenant.
+    worker: usize,
+    /// Time from the start of the run until a worker picked the tenant up.
+    queued_s: f64,
+    /// Every attempt, the last one giving `status`.
+    attempts: Vec<AttemptRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AttemptRecord {
+    attempt: u32,
+    status: String,
+    /// Failure class the retry policy saw; `None` for a successful attempt.
+    failure: Option<FailureClass>,
+    worker: usize,
+    started_s: f64,
+    duration_s: f64,
+    /// Backoff before the next attempt; absent on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_in_ms: Option<u64>,
 }
 
 /// Compact row used in dashboards
@@ -143,21 +254,22 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::VerifyEnv => verify::verify_environment()?,
 
-        Commands::Run { all, proof, parallel, backend, path, vm_timeout } => {
+        Commands::Run { all, proof, parallel, backend, path, input, batch, results } => {
 
     println!("🧭 Backend selected: {}", backend);
 
-    if backend == "firecracker" {
-    println!("🧭 Backend selected: firecracker");
-    firecracker_adapter::launch_microvm_with_timeout(
-
-        "firecracker_assets/vmlinux",
-        "firecracker_assets/rootfs.ext4",
-        vm_timeout,
-    )?;
-}
-
-
+    let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+    let sandbox = select_backend(&backend, &policy)?;
+
+    // Request/response payloads target one tenant, never the whole fleet.
+    let payloads = match (&input, &batch) {
+        (Some(f), _) => Some(nc_exec::read_payloads(f, false)?),
+        (_, Some(f)) => Some(nc_exec::read_payloads(f, true)?),
+        _ => None,
+    };
+    if all && payloads.is_some() {
+        return Err(anyhow!("--input / --batch run a single tenant; pass its path instead of --all"));
+    }
 
     if all {
         let start_total = Instant::now();
@@ -166,10 +278,12 @@ fn main() -> Result<()> {
 
 
                 let modules_dir = PathBuf::from("modules");
-                let entries: Vec<_> = fs::read_dir(&modules_dir)?
+                let mut entries: Vec<PathBuf> = fs::read_dir(&modules_dir)?
                     .filter_map(|e| e.ok())
-                    .filter(|e| e.path().is_dir())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir())
                     .collect();
+                entries.sort();
 
                 let mut parallel = if parallel == 0 {
                     std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
@@ -182,50 +296,58 @@ fn main() -> Result<()> {
 
                 println!("🧩 Running {} tenants with parallelism = {}", entries.len(), parallel);
 
-                let (tx, rx) = mpsc::channel();
                 let mut timing: Vec<TenantTiming> = vec![];
-
-                for chunk in entries.chunks(parallel) {
-                    let mut handles = vec![];
-
-                    for entry in chunk {
-                        let tx = tx.clone();
-                        let tenant_dir = entry.path();
-                        let tenant_name = entry.file_name().to_string_lossy().into_owned();
-                        let proof_mode = proof;
-
-                        handles.push(thread::spawn(move || {
-                            let t0 = Instant::now();
-                            let result = verify::verify_and_run(&tenant_dir, proof_mode);
-                            let elapsed = t0.elapsed().as_secs_f64();
-
-                            match result {
-                                Ok(sha) => { let _ = tx.send(TenantTiming { name: tenant_name, sha, duration_s: elapsed, status: "ok".into() }); }
-                                Err(e)   => { let _ = tx.send(TenantTiming { name: tenant_name, sha: "<none>".into(), duration_s: elapsed, status: format!("error: {}", e) }); }
-                            };
-                        }));
-                    }
-
-                    for handle in handles { let _ = handle.join(); }
-                    thread::sleep(Duration::from_millis(250));
-                }
-
-                drop(tx);
-                for tenant in rx {
-                    println!("{:<20} {:<40} ⏱️  {:.2}s", tenant.name, tenant.status, tenant.duration_s);
+                let job = tenant_job(&sandbox, &policy, proof);
+                scheduler::run_queue(entries, parallel, tenant_rules(&policy), job, |done| {
+                    let name = done.job.file_name()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    let attempts: Vec<AttemptRecord> = done.attempts.iter().map(|a| AttemptRecord {
+                        attempt: a.number,
+                        status: attempt_status(a),
+                        failure: attempt_failure(a),
+                        worker: a.worker,
+                        started_s: a.started.as_secs_f64(),
+                        duration_s: a.elapsed.as_secs_f64(),
+                        retry_in_ms: a.retry_in.map(|d| d.as_millis() as u64),
+                    }).collect();
+                    let first = &done.attempts[0];
+                    let last = done.attempts.last().unwrap();
+                    let (sha, backend, exec_duration_s) = match &last.result {
+                        Some(Ok(run)) => (run.sha.clone(), run.exec.backend.clone(), run.exec_duration_s),
+                        _ => ("<none>".into(), sandbox.name().into(), 0.0),
+                    };
+                    let tenant = TenantTiming {
+                        name, sha, backend, exec_duration_s,
+                        status: attempt_status(last),
+                        duration_s: done.attempts.iter().map(|a| a.elapsed.as_secs_f64()).sum(),
+                        worker: last.worker,
+                        queued_s: first.started.as_secs_f64(),
+                        attempts,
+                    };
+                    let retried = match tenant.attempts.len() {
+                        1 => String::new(),
+                        n => format!(", {} attempts", n),
+                    };
+                    println!("{:<20} {:<40} ⏱️  {:.2}s (worker {}, queued {:.2}s{})",
+                        tenant.name, tenant.status, tenant.duration_s, tenant.worker, tenant.queued_s, retried);
                     timing.push(tenant);
-                }
+                });
 
                 let total_time = start_total.elapsed().as_secs_f64();
-                let avg_time = if !timing.is_empty() {
-                    timing.iter().map(|t| t.duration_s).sum::<f64>() / timing.len() as f64
-                } else { 0.0 };
+                let busy_time: f64 = timing.iter().map(|t| t.duration_s).sum();
+                let timeouts = timing.iter().filter(|t| t.status == "timeout").count();
+                let retries: usize = timing.iter().map(|t| t.attempts.len() - 1).sum();
+                let avg_time = if !timing.is_empty() { busy_time / timing.len() as f64 } else { 0.0 };
 
                 println!("\n📊 Performance Summary");
                 println!("────────────────────────────────────────────");
                 println!("Tenants Executed : {}", timing.len());
                 println!("Total Time       : {:.2}s", total_time);
                 println!("Average per Tenant: {:.2}s", avg_time);
+                println!("Busy Time        : {:.2}s", busy_time);
+                println!("Timeouts         : {}", timeouts);
+                println!("Retries          : {}", retries);
                 println!("Parallel Threads : {}", parallel);
                 println!("────────────────────────────────────────────");
                 println!("✨ Night Core parallel orchestration complete.\n");
@@ -236,6 +358,9 @@ fn main() -> Result<()> {
                     tenants_executed: timing.len(),
                     total_time_s: total_time,
                     avg_time_s: avg_time,
+                    busy_time_s: busy_time,
+                    timeouts,
+                    retries,
                     parallel_threads: parallel,
                     tenants: timing.clone(),
                 };
@@ -243,34 +368,7 @@ fn main() -> Result<()> {
                 fs::write("logs/orchestration_report.json", serde_json::to_string_pretty(&report)?)?;
                 println!("📁 Saved orchestration report → logs/orchestration_report.json");
 
-                // lightweight live dashboard (kept from before)
-                let mut html = String::from(
-                    "<!doctype html><html><head><meta charset='utf-8'>\
-                     <meta http-equiv='refresh' content='10'>\
-                     <title>Night Core Dashboard</title>\
-                     <style>body{font-family:Arial;background:#0b0e13;color:#e0e0e0;}\
-                     h2{color:#74c0fc;}table{width:100%;border-collapse:collapse;}\
-                     th,td{padding:6px;border-bottom:1px solid #333;}\
-                     tr:hover{background:#1a1f2a;} .ok{color:#74ff7e;} .err{color:#ff6b6b;}\
-                     </style></head><body>"
-                );
-                html.push_str(&format!(
-                    "<h2>🧩 Night Core™ Proof Dashboard</h2>\
-                     <p><b>Timestamp:</b> {}<br>\
-                     <b>Tenants:</b> {} &nbsp; <b>Parallel:</b> {} &nbsp; \
-                     <b>Total:</b> {:.2}s &nbsp; <b>Avg:</b> {:.2}s</p>\
-                     <table><tr><th>Tenant</th><th>SHA-256</th><th>Status</th><th>Duration (s)</th></tr>",
-                    timestamp, report.tenants_executed, parallel, report.total_time_s, report.avg_time_s
-                ));
-                for t in &report.tenants {
-                    let cls = if t.status == "ok" { "ok" } else { "err" };
-                    html.push_str(&format!(
-                        "<tr><td>{}</td><td>{}</td><td class='{}'>{}</td><td>{:.2}</td></tr>",
-                        t.name, t.sha, cls, t.status, t.duration_s
-                    ));
-                }
-                html.push_str("</table><br><p>🔒 Verified by Night Core AUFS Proof System</p></body></html>");
-                fs::write("logs/nightcore_dashboard.html", html)?;
+                write_live_dashboard(&report)?;
                 println!("🖥️  Updated proof dashboard → logs/nightcore_dashboard.html");
 
                 if let Err(e) = open::that("logs/nightcore_dashboard.html") {
@@ -288,16 +386,86 @@ fn main() -> Result<()> {
                 }
 
                 let t0 = Instant::now();
-                let sha = verify::verify_and_run(&p, proof)?;
-                let elapsed = t0.elapsed().as_secs_f64();
-                println!("✅ {} executed successfully (sha {}, {:.2}s)", tenant_name, sha, elapsed);
+                if let Some(payloads) = payloads {
+                    let results = results
+                        .unwrap_or_else(|| PathBuf::from("logs").join(&tenant_name).join("results.jsonl"));
+                    let summary = verify::verify_and_run_batch(&p, sandbox.as_ref(), &policy, &payloads, &results)?;
+                    let icon = if summary.ok == summary.records { "✅" } else { "⚠️" };
+                    println!(
+                        "{} {} batch finished: {}/{} ok (sha {}, total {:.2}s) → {}",
+                        icon, tenant_name, summary.ok, summary.records, summary.sha,
+                        t0.elapsed().as_secs_f64(), results.display()
+                    );
+                } else {
+                    let run = verify::verify_and_run(&p, proof, sandbox.as_ref(), &policy, None, &CancelToken::default(), 1)?;
+                    let elapsed = t0.elapsed().as_secs_f64();
+                    let status = orchestration_status(&run.exec.outcome);
+                    let icon = if status == "ok" { "✅" } else { "⚠️" };
+                    println!(
+                        "{} {} finished: {} (sha {}, backend {}, exec {:.2}s, total {:.2}s)",
+                        icon, tenant_name, status, run.sha, run.exec.backend, run.exec_duration_s, elapsed
+                    );
+                }
             } else {
                 println!("⚙️ Usage: nightcore run --all [--proof] [--parallel N] OR --path <tenant_dir> [--proof]");
             }
         }
 
         Commands::Sign { dir, key } => sign_tenant::sign_tenant(&dir, &key)?,
-        Commands::Inspect { dir } => verify::inspect_manifest(&dir)?,
+        Commands::Inspect { dir } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            verify::inspect_manifest(&dir, &policy)?;
+        }
+        Commands::Replay { path, against, backend } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            let sandbox = select_backend(&backend, &policy)?;
+            verify::replay(&path, sandbox.as_ref(), &policy, against.as_deref())?;
+        }
+        Commands::Invoke { tenant, export, backend, args } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            let sandbox = select_backend(&backend, &policy)?;
+            let dir = PathBuf::from("modules").join(&tenant);
+            if !dir.is_dir() {
+                return Err(anyhow!("no tenant '{}' under modules/", tenant));
+            }
+            if let Err(e) = verify::ensure_pubkey_sync(dir.to_str().unwrap_or_default(), &tenant) {
+                eprintln!("⚠️ Pubkey sync failed: {}", e);
+            }
+            let invoke = verify::Invoke { export, args };
+            let run = verify::verify_and_run(&dir, false, sandbox.as_ref(), &policy, Some(&invoke), &CancelToken::default(), 1)?;
+            let status = orchestration_status(&run.exec.outcome);
+            let icon = if status == "ok" { "✅" } else { "⚠️" };
+            println!("{} {} {} finished: {} (sha {}, exec {:.2}s)",
+                icon, tenant, invoke.export, status, run.sha, run.exec_duration_s);
+        }
+        #[cfg(unix)]
+        Commands::Serve { backend, parallel, socket } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            let sandbox = select_backend(&backend, &policy)?;
+            let parallel = if parallel == 0 {
+                std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+            } else { parallel };
+            let socket = socket.unwrap_or_else(|| policy.serve.socket.clone());
+            serve::serve(sandbox, policy, parallel, &socket)?;
+        }
+        #[cfg(unix)]
+        Commands::Status { socket, json } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            let socket = socket.unwrap_or(policy.serve.socket);
+            let status = serve::query_status(&socket)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                serve::print_status(&status);
+            }
+        }
+        #[cfg(target_os = "linux")]
+        Commands::Watch { run, backend } => {
+            let policy = policy::load(&PathBuf::from(policy::DEFAULT_POLICY_PATH))?;
+            let sandbox = select_backend(&backend, &policy)?;
+            let run = run || policy.watch.run_on_change;
+            watch::watch(sandbox, policy, run)?;
+        }
         Commands::ExportPubkeyHashes => export_pubkeys()?,
 
         Commands::ExportDashboard { diff } => {
@@ -350,6 +518,12 @@ fn main() -> Result<()> {
                 )?;
                 println!("\n💾 Exported proof history → logs/{}_proof_history.json", tenant);
 
+                let runs = state.get_json::<Vec<Value>>("run_history")?.unwrap_or_default();
+                if let Some(last) = runs.last() {
+                    println!("\n🛰️  Scheduled runs: {} (nightcore serve), last:", runs.len());
+                    println!("{}", serde_json::to_string_pretty(last)?);
+                }
+
                 if summary {
                     // compute quick stats
                     let total = hist.len();
@@ -405,6 +579,171 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Resolve the `--backend` flag to the sandbox that executes verified tenants.
+fn select_backend(name: &str, policy: &policy::Policy) -> Result<Arc<dyn SandboxBackend + Send + Sync>> {
+    let wasmtime = || -> Result<WasmtimeBackend> {
+        let tuning = &policy.engine;
+        let backend = WasmtimeBackend::new(&EngineOptions {
+            pooling: tuning.pooling,
+            pool_slots: tuning.pool_slots,
+            pool_max_memory_mb: tuning.pool_max_memory_mb,
+            memory_reservation_mb: tuning.memory_reservation_mb,
+        })?;
+        Ok(if policy.cache.enabled { backend.with_cache_dir(&policy.cache.dir) } else { backend })
+    };
+    match name {
+        "wasmtime" => Ok(Arc::new(wasmtime()?)),
+        // Until the guest agent lands a microVM cannot run a tenant module, and
+        // quietly running it under Wasmtime instead would misreport the sandbox.
+        "firecracker" => Err(anyhow!("the firecracker backend cannot execute tenant modules yet (no guest agent); use --backend wasmtime")),
+        other => Err(anyhow!("unknown backend '{}' (expected wasmtime | firecracker)", other)),
+    }
+}
+
+/// Lightweight live dashboard for the latest `run --all`, with any
+/// unresolved watch alerts on top.
+fn write_live_dashboard(report: &OrchestrationReport) -> Result<()> {
+    let mut html = String::from(
+        "<!doctype html><html><head><meta charset='utf-8'>\
+         <meta http-equiv='refresh' content='10'>\
+         <title>Night Core Dashboard</title>\
+         <style>body{font-family:Arial;background:#0b0e13;color:#e0e0e0;}\
+         h2{color:#74c0fc;}table{width:100%;border-collapse:collapse;}\
+         th,td{padding:6px;border-bottom:1px solid #333;}\
+         tr:hover{background:#1a1f2a;} .ok{color:#74ff7e;} .err{color:#ff6b6b;}\
+         </style></head><body>"
+    );
+    html.push_str(&format!(
+        "<h2>🧩 Night Core™ Proof Dashboard</h2>{}\
+         <p><b>Timestamp:</b> {}<br>\
+         <b>Tenants:</b> {} &nbsp; <b>Parallel:</b> {} &nbsp; \
+         <b>Total:</b> {:.2}s &nbsp; <b>Avg:</b> {:.2}s</p>\
+         <table><tr><th>Tenant</th><th>SHA-256</th><th>Backend</th><th>Status</th><th>Exec (s)</th><th>Duration (s)</th></tr>",
+        alert_banner(), report.timestamp, report.tenants_executed, report.parallel_threads, report.total_time_s, report.avg_time_s
+    ));
+    for t in &report.tenants {
+        let cls = if t.status == "ok" { "ok" } else { "err" };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class='{}'>{}</td><td>{:.2}</td><td>{:.2}</td></tr>",
+            escape_html(&t.name), escape_html(&t.sha), escape_html(&t.backend), cls, escape_html(&t.status),
+            t.exec_duration_s, t.duration_s
+        ));
+    }
+    html.push_str("</table><br><p>🔒 Verified by Night Core AUFS Proof System</p></body></html>");
+    fs::write("logs/nightcore_dashboard.html", html)?;
+    Ok(())
+}
+
+/// Tenant names, digests read back from state and error text all end up in
+/// the dashboards; none of it is trusted as markup.
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '\'' => out.push_str("&#39;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Re-render the live dashboard from the last saved orchestration report,
+/// e.g. after `nightcore watch` raised or cleared an alert.
+#[cfg(target_os = "linux")]
+fn refresh_live_dashboard() -> Result<()> {
+    let report = fs::read_to_string("logs/orchestration_report.json")
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    fs::create_dir_all("logs")?;
+    write_live_dashboard(&report)
+}
+
+/// Dashboard warning for tenants `nightcore watch` caught failing
+/// verification after a change; empty when there are none.
+fn alert_banner() -> String {
+    #[cfg(target_os = "linux")]
+    return watch::alert_banner();
+    #[cfg(not(target_os = "linux"))]
+    String::new()
+}
+
+/// Map a backend outcome onto the status shown in reports and dashboards
+/// (anything other than "ok" renders as a failure).
+fn orchestration_status(outcome: &ExecOutcome) -> String {
+    match outcome {
+        ExecOutcome::Exited { code: 0 } => "ok".into(),
+        ExecOutcome::Exited { code } => format!("exit {}", code),
+        ExecOutcome::Trapped { trap } if trap == "interrupt" => "timeout".into(),
+        ExecOutcome::Trapped { trap } if trap == "out_of_fuel" => "out_of_fuel".into(),
+        ExecOutcome::Trapped { trap } => format!("trap: {}", trap),
+        ExecOutcome::MissingEntrypoint { entrypoint } => format!("missing entrypoint '{}'", entrypoint),
+        ExecOutcome::HostError { message } => format!("error: {}", message),
+    }
+}
+
+type TenantAttempt = scheduler::Attempt<Result<verify::RunOutcome>>;
+
+/// Queue job for one tenant directory: the full verify → execute → proof path.
+fn tenant_job(
+    sandbox: &Arc<dyn SandboxBackend + Send + Sync>,
+    policy: &policy::Policy,
+    proof: bool,
+) -> impl Fn(&PathBuf, u32, &CancelToken) -> Result<verify::RunOutcome> + Send + Sync + 'static {
+    let (sandbox, policy) = (Arc::clone(sandbox), policy.clone());
+    move |dir: &PathBuf, attempt: u32, cancel: &CancelToken| {
+        verify::verify_and_run(dir, proof, sandbox.as_ref(), &policy, None, cancel, attempt)
+    }
+}
+
+/// Orchestration deadline and retry policy for queued tenant runs.
+fn tenant_rules(
+    policy: &policy::Policy,
+) -> scheduler::Rules<
+    impl Fn(&PathBuf) -> Option<Duration> + Sync + '_,
+    impl Fn(&PathBuf, &TenantAttempt) -> Option<Duration> + Sync + '_,
+> {
+    scheduler::Rules {
+        deadline: move |dir: &PathBuf| verify::orchestration_deadline(dir, policy),
+        retry: move |dir: &PathBuf, attempt: &TenantAttempt| {
+            let class = attempt_failure(attempt)?;
+            let backoff = verify::retry_policy(dir, policy).retry_after(class, attempt.number)?;
+            println!("🔁 {}: attempt {} failed ({}), retrying in {} ms",
+                dir.display(), attempt.number, class, backoff.as_millis());
+            Some(backoff)
+        },
+    }
+}
+
+/// Report status of one scheduled attempt.
+fn attempt_status(attempt: &TenantAttempt) -> String {
+    // However the cancelled run ended, the orchestrator gave up on it.
+    if attempt.timed_out {
+        return "timeout".into();
+    }
+    match &attempt.result {
+        Some(Ok(run)) => orchestration_status(&run.exec.outcome),
+        Some(Err(e)) => format!("error: {:#}", e),
+        None => "error: tenant run panicked".into(),
+    }
+}
+
+/// Failure class the retry policy judges an attempt by; `None` if it succeeded.
+fn attempt_failure(attempt: &TenantAttempt) -> Option<FailureClass> {
+    if attempt.timed_out {
+        return Some(FailureClass::Timeout);
+    }
+    match &attempt.result {
+        Some(Ok(run)) => FailureClass::of_outcome(&run.exec.outcome),
+        Some(Err(e)) => Some(verify::failure_class(e)),
+        None => Some(FailureClass::Backend),
+    }
+}
+
 /// 🔍 Pubkey export helper
 fn export_pubkeys() -> Result<()> {
     println!("🔍 Exporting pubkey hashes for upgrade manifest:");
@@ -466,7 +805,7 @@ fn build_global_history_dashboard(show_diff: bool) -> Result<()> {
 
         let mut header = format!(
             "<div class='tenant'><h3>🧩 {name}</h3><p>Total proofs: <b>{n}</b></p>",
-            name = t, n = rows.len()
+            name = escape_html(&t), n = rows.len()
         );
 
         if show_diff {
@@ -480,7 +819,7 @@ fn build_global_history_dashboard(show_diff: bool) -> Result<()> {
             let vcls = if r.verified { "ok" } else { "err" };
             header.push_str(&format!(
                 "<tr><td>{}</td><td>{}</td><td class='mono'>{}</td><td>{}</td><td class='{}'>{}</td></tr>",
-                i+1, r.timestamp, r.sha256, r.size, vcls, r.verified
+                i+1, escape_html(&r.timestamp), escape_html(&r.sha256), r.size, vcls, r.verified
             ));
         }
         header.push_str("</table></div>");
@@ -513,6 +852,7 @@ fn build_global_history_dashboard(show_diff: bool) -> Result<()> {
 <body>
   <h1>Night Core™ — Historical Proof Ledger</h1>
   <p>Generated: {ts} &nbsp; • &nbsp; Mode: {mode}</p>
+  {alerts}
   {sections}
   <br/>
   <p style="opacity:.7">🔒 All records minted from per-tenant sled state. SHA changes highlight module mutations between last two runs.</p>
@@ -520,6 +860,7 @@ fn build_global_history_dashboard(show_diff: bool) -> Result<()> {
 </html>"#,
         ts = Utc::now().to_rfc3339(),
         mode = if show_diff { "diff" } else { "basic" },
+        alerts = alert_banner(),
         sections = sections
     );
 
@@ -561,11 +902,11 @@ fn render_diff_block(last: &Option<ProofRow>, prev: &Option<ProofRow>) -> String
                 sha_changed = sha_changed,
                 ver_cls = ver_class,
                 ver_changed = ver_changed,
-                lts = l.timestamp,
-                lsha = l.sha256,
+                lts = escape_html(&l.timestamp),
+                lsha = escape_html(&l.sha256),
                 lok = l.verified,
-                pts = p.timestamp,
-                psha = p.sha256,
+                pts = escape_html(&p.timestamp),
+                psha = escape_html(&p.sha256),
                 pok = p.verified
             )
         }
@@ -0,0 +1,213 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// ===========================================================
+/// 📜 Night Core Policy — configs/policy.default.toml
+/// ===========================================================
+/// Only the sections the runtime acts on are modelled; the rest of the
+/// file (guardian, aufs, nightmesh, …) is ignored here.
+pub const DEFAULT_POLICY_PATH: &str = "configs/policy.default.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub orchestration: Orchestration,
+    #[serde(default)]
+    pub output: Output,
+    #[serde(default)]
+    pub permissions: Permissions,
+    #[serde(default)]
+    pub engine: EngineTuning,
+    #[serde(default)]
+    pub cache: Cache,
+    #[serde(default)]
+    pub kv: Kv,
+    /// Host-wide compiler profile; manifests override it field by field.
+    #[serde(default)]
+    pub compiler: nc_exec::CompilerProfile,
+    /// Host-wide retry policy for failed tenant runs; manifests override it
+    /// field by field.
+    #[serde(default)]
+    pub retry: nc_exec::RetryPolicy,
+    #[serde(default)]
+    pub serve: Serve,
+    #[serde(default)]
+    pub watch: Watch,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Orchestration {
+    pub parallel_limit: usize,
+    pub max_memory_mb: u64,
+    pub max_table_elements: u64,
+    pub max_instances: u64,
+    /// Per-tenant orchestration deadline (verify + run) when the manifest sets
+    /// no `timeout_ms`; 0 disables it.
+    pub timeout_seconds: u64,
+}
+
+impl Default for Orchestration {
+    fn default() -> Self {
+        Self {
+            parallel_limit: 2,
+            max_memory_mb: 256,
+            max_table_elements: 10_000,
+            max_instances: 16,
+            timeout_seconds: 15,
+        }
+    }
+}
+
+/// Guest stdout/stderr capture caps (bytes) and terminal echo.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Output {
+    pub max_stdout_bytes: usize,
+    pub max_stderr_bytes: usize,
+    pub echo: bool,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self { max_stdout_bytes: 1024 * 1024, max_stderr_bytes: 1024 * 1024, echo: true }
+    }
+}
+
+/// Host-wide permission switches that tenant manifests cannot override.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+    pub allow_stdout: bool,
+    /// Covers every `fs:read:*` / `fs:write:*` grant.
+    pub allow_fs_read: bool,
+    pub allow_net: bool,
+    /// Allow manifests to request `inherit_env` (host environment leaks into the guest).
+    pub allow_host_env: bool,
+    /// Covers the `kv` grant (tenant state through the `nightcore` host module).
+    pub allow_kv: bool,
+    /// Host directories manifest mounts may expose; a mount's `host` path must
+    /// lie inside one of them. Manifests are unsigned, so this is what keeps
+    /// them from mounting arbitrary host paths.
+    pub mount_roots: Vec<PathBuf>,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            allow_stdout: true,
+            allow_fs_read: true,
+            allow_net: false,
+            allow_host_env: false,
+            allow_kv: true,
+            mount_roots: vec![PathBuf::from("sandbox")],
+        }
+    }
+}
+
+/// Shared Wasmtime engine tuning (instance pool, address-space reservation).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EngineTuning {
+    pub pooling: bool,
+    pub pool_slots: u32,
+    pub pool_max_memory_mb: u64,
+    pub memory_reservation_mb: Option<u64>,
+}
+
+impl Default for EngineTuning {
+    fn default() -> Self {
+        Self { pooling: false, pool_slots: 16, pool_max_memory_mb: 256, memory_reservation_mb: None }
+    }
+}
+
+/// Precompiled-artifact cache for verified modules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Cache {
+    pub enabled: bool,
+    pub dir: PathBuf,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self { enabled: true, dir: PathBuf::from("cache/modules") }
+    }
+}
+
+/// Size caps on guest key/value state (per tenant).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Kv {
+    pub max_key_bytes: usize,
+    pub max_value_bytes: usize,
+    pub max_total_bytes: u64,
+}
+
+impl Default for Kv {
+    fn default() -> Self {
+        let limits = nc_exec::KvLimits::default();
+        Self {
+            max_key_bytes: limits.max_key_bytes,
+            max_value_bytes: limits.max_value_bytes,
+            max_total_bytes: limits.max_total_bytes,
+        }
+    }
+}
+
+/// `nightcore serve`: status socket and tenant schedules.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Serve {
+    /// Unix socket `nightcore status` queries.
+    pub socket: PathBuf,
+    /// Schedule for tenants whose manifest sets none; unset leaves them idle.
+    pub default_schedule: Option<nc_exec::Schedule>,
+    /// Per-tenant schedules, taking precedence over the manifests.
+    pub schedules: BTreeMap<String, nc_exec::Schedule>,
+}
+
+impl Default for Serve {
+    fn default() -> Self {
+        Self { socket: PathBuf::from("state/nightcore.sock"), default_schedule: None, schedules: BTreeMap::new() }
+    }
+}
+
+/// `nightcore watch`: what to do once a changed tenant re-verifies.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Watch {
+    /// Run the tenant after it re-verifies (`nightcore watch --run` forces it).
+    pub run_on_change: bool,
+    /// Quiet period after a tenant's last change before it is re-verified, so
+    /// a module and its signature written one after the other are checked
+    /// together.
+    pub debounce_ms: u64,
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Self { run_on_change: false, debounce_ms: 500 }
+    }
+}
+
+/// Load the policy file, falling back to built-in defaults when it is absent.
+pub fn load(path: &Path) -> Result<Policy> {
+    if !path.exists() {
+        return Ok(Policy::default());
+    }
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("reading policy {}", path.display()))?;
+    let policy: Policy = toml::from_str(&raw)
+        .with_context(|| format!("parsing policy {}", path.display()))?;
+    let problems = policy.retry.problems();
+    if !problems.is_empty() {
+        return Err(anyhow!("invalid policy {}: {}", path.display(), problems.join("; ")));
+    }
+    Ok(policy)
+}
@@ -0,0 +1,365 @@
+use nc_exec::CancelToken;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+// ===========================================================
+// 🧵 Work Queue — bounded worker pool for `run --all`
+// ===========================================================
+// Workers pull the next job the moment they are free, so a slow tenant only
+// ever occupies its own slot, and a tenant past its deadline is cancelled.
+// One that will not stop is abandoned so its result can be reported, but its
+// thread keeps counting against the pool until it exits, and its job is not
+// retried before then. A failed job the retry policy wants again goes back on
+// the queue with a not-before time; its backoff never holds a worker.
+
+/// How long a cancelled job gets to wind down before its thread is abandoned.
+pub const CANCEL_GRACE: Duration = Duration::from_secs(2);
+
+/// Abandoned threads exit without signalling the queue, so workers waiting
+/// on one re-check this often.
+const STRAGGLER_POLL: Duration = Duration::from_millis(50);
+
+/// One run of a job.
+pub struct Attempt<R> {
+    /// 1-based.
+    pub number: u32,
+    /// `None` when the job panicked, or overran its deadline and did not stop
+    /// within [`CANCEL_GRACE`].
+    pub result: Option<R>,
+    /// The job hit its deadline and was cancelled.
+    pub timed_out: bool,
+    /// Worker slot (0-based) that ran the attempt.
+    pub worker: usize,
+    /// When the attempt started, relative to the start of the run.
+    pub started: Duration,
+    /// Time the attempt took (up to the point it was given up on).
+    pub elapsed: Duration,
+    /// Backoff before the next attempt; `None` on the final one.
+    pub retry_in: Option<Duration>,
+}
+
+/// A finished job with every attempt made at it, the final one last.
+pub struct Done<T, R> {
+    pub job: T,
+    pub attempts: Vec<Attempt<R>>,
+}
+
+/// Per-job callbacks consulted by the workers.
+pub struct Rules<D, P> {
+    /// Deadline for one attempt; past it the attempt's token is cancelled.
+    pub deadline: D,
+    /// Backoff before another attempt, or `None` to stop after this one.
+    pub retry: P,
+}
+
+struct Pending<T, R> {
+    job: T,
+    attempts: Vec<Attempt<R>>,
+    ready_at: Instant,
+    /// The previous attempt's thread, if it was abandoned.
+    previous: Option<Straggler>,
+}
+
+struct Queue<T, R> {
+    pending: VecDeque<Pending<T, R>>,
+    /// Jobs being run; each may come back for another attempt.
+    in_flight: usize,
+    /// Abandoned attempts whose threads may still be running.
+    stragglers: Vec<Straggler>,
+}
+
+/// An abandoned attempt's thread; `running` is cleared when it exits.
+#[derive(Clone)]
+struct Straggler(Arc<AtomicBool>);
+
+impl Straggler {
+    fn running(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Held by an attempt's thread; marks it exited even if the job panics.
+struct Exited(Arc<AtomicBool>);
+
+impl Drop for Exited {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Run `work` over every job on at most `workers` threads. `on_done` is
+/// called on the calling thread as each job completes (completion order, not
+/// queue order). Returns once every job has finished or been given up on.
+pub fn run_queue<T, R, W, D, P, F>(jobs: Vec<T>, workers: usize, rules: Rules<D, P>, work: W, mut on_done: F)
+where
+    T: Clone + Send + 'static,
+    R: Send + 'static,
+    W: Fn(&T, u32, &CancelToken) -> R + Send + Sync + 'static,
+    D: Fn(&T) -> Option<Duration> + Sync,
+    P: Fn(&T, &Attempt<R>) -> Option<Duration> + Sync,
+    F: FnMut(Done<T, R>),
+{
+    let workers = workers.clamp(1, jobs.len().max(1));
+    let start = Instant::now();
+    let queue = Mutex::new(Queue {
+        pending: jobs.into_iter().map(|job| Pending { job, attempts: vec![], ready_at: start, previous: None }).collect(),
+        in_flight: 0,
+        stragglers: vec![],
+    });
+    let changed = Condvar::new();
+    let work = Arc::new(work);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for worker in 0..workers {
+            let tx = tx.clone();
+            let (queue, changed, work, rules) = (&queue, &changed, &work, &rules);
+            scope.spawn(move || {
+                while let Some(mut item) = next_ready(queue, changed, workers) {
+                    let number = item.attempts.len() as u32 + 1;
+                    let limit = (rules.deadline)(&item.job);
+                    let started = start.elapsed();
+                    let t0 = Instant::now();
+                    let (result, timed_out, straggler) = run_attempt(&item.job, number, Arc::clone(work), limit);
+                    let mut attempt = Attempt {
+                        number, result, timed_out, worker, started,
+                        elapsed: t0.elapsed(),
+                        retry_in: None,
+                    };
+                    attempt.retry_in = (rules.retry)(&item.job, &attempt);
+                    item.attempts.push(attempt);
+
+                    let mut q = queue.lock().unwrap();
+                    q.in_flight -= 1;
+                    q.stragglers.extend(straggler.clone());
+                    match item.attempts.last().and_then(|a| a.retry_in) {
+                        Some(backoff) => {
+                            item.ready_at = Instant::now() + backoff;
+                            item.previous = straggler;
+                            q.pending.push_back(item);
+                        }
+                        None => {
+                            let _ = tx.send(Done { job: item.job, attempts: item.attempts });
+                        }
+                    }
+                    drop(q);
+                    changed.notify_all();
+                }
+                changed.notify_all();
+            });
+        }
+        drop(tx);
+        for done in rx {
+            on_done(done);
+        }
+    });
+}
+
+/// Take the next job whose backoff has passed and whose previous attempt has
+/// exited, once fewer than `workers` attempts (abandoned ones included) are
+/// running. Waits while jobs are backing off or still running (they may be
+/// re-queued). `None` once all are done.
+fn next_ready<T, R>(queue: &Mutex<Queue<T, R>>, changed: &Condvar, workers: usize) -> Option<Pending<T, R>> {
+    let mut q = queue.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        q.stragglers.retain(Straggler::running);
+        if q.in_flight + q.stragglers.len() < workers {
+            let ready = |p: &Pending<T, R>| p.ready_at <= now && !p.previous.as_ref().is_some_and(Straggler::running);
+            if let Some(pos) = q.pending.iter().position(ready) {
+                q.in_flight += 1;
+                return q.pending.remove(pos);
+            }
+        }
+        if q.pending.is_empty() && q.in_flight == 0 {
+            return None;
+        }
+        let backoff = q.pending.iter().filter(|p| p.ready_at > now).map(|p| p.ready_at - now).min();
+        let poll = (!q.stragglers.is_empty()).then_some(STRAGGLER_POLL);
+        q = match backoff.into_iter().chain(poll).min() {
+            Some(wait) => changed.wait_timeout(q, wait).unwrap().0,
+            None => changed.wait(q).unwrap(),
+        };
+    }
+}
+
+/// Run one attempt on a detached thread and wait for it, cancelling it once
+/// `limit` passes. An attempt that ignores cancellation is left behind and
+/// returned as a [`Straggler`].
+fn run_attempt<T, R, W>(job: &T, number: u32, work: Arc<W>, limit: Option<Duration>) -> (Option<R>, bool, Option<Straggler>)
+where
+    T: Clone + Send + 'static,
+    R: Send + 'static,
+    W: Fn(&T, u32, &CancelToken) -> R + Send + Sync + 'static,
+{
+    let cancel = CancelToken::new();
+    let (done_tx, done_rx) = mpsc::channel();
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let (job, cancel, exited) = (job.clone(), cancel.clone(), Exited(Arc::clone(&running)));
+        thread::spawn(move || {
+            let _exited = exited;
+            let _ = done_tx.send(work(&job, number, &cancel));
+        });
+    }
+    let Some(limit) = limit else { return (done_rx.recv().ok(), false, None) };
+    match done_rx.recv_timeout(limit) {
+        Ok(result) => (Some(result), false, None),
+        Err(RecvTimeoutError::Timeout) => {
+            cancel.cancel();
+            match done_rx.recv_timeout(CANCEL_GRACE) {
+                Ok(result) => (Some(result), true, None),
+                Err(_) => (None, true, Some(Straggler(running))),
+            }
+        }
+        Err(RecvTimeoutError::Disconnected) => (None, false, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_deadline(_: &u32) -> Option<Duration> {
+        None
+    }
+
+    fn collect<R, W, D, P>(jobs: Vec<u32>, workers: usize, rules: Rules<D, P>, work: W) -> Vec<Done<u32, R>>
+    where
+        R: Send + 'static,
+        W: Fn(&u32, u32, &CancelToken) -> R + Send + Sync + 'static,
+        D: Fn(&u32) -> Option<Duration> + Sync,
+        P: Fn(&u32, &Attempt<R>) -> Option<Duration> + Sync,
+    {
+        let mut done = vec![];
+        run_queue(jobs, workers, rules, work, |d| done.push(d));
+        done
+    }
+
+    #[test]
+    fn runs_every_job_on_bounded_workers() {
+        let rules = Rules { deadline: no_deadline, retry: |_: &u32, _: &Attempt<u32>| None };
+        let mut done = collect((0..6).collect(), 2, rules, |job, _, _| job * 10);
+        done.sort_by_key(|d| d.job);
+        assert_eq!(done.iter().map(|d| d.job).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+        for d in &done {
+            assert_eq!(d.attempts.len(), 1);
+            assert_eq!(d.attempts[0].result, Some(d.job * 10));
+            assert!(d.attempts[0].worker < 2);
+        }
+    }
+
+    #[test]
+    fn retries_until_the_rule_stops() {
+        let rules = Rules {
+            deadline: no_deadline,
+            retry: |_: &u32, a: &Attempt<bool>| (a.result == Some(false)).then(|| Duration::from_millis(10)),
+        };
+        // Fails twice, then succeeds.
+        let done = collect(vec![7], 1, rules, |_, number, _| number >= 3);
+        let attempts = &done[0].attempts;
+        assert_eq!(attempts.iter().map(|a| a.number).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(attempts[0].retry_in, Some(Duration::from_millis(10)));
+        assert_eq!(attempts[2].retry_in, None);
+        assert_eq!(attempts[2].result, Some(true));
+        assert!(attempts[1].started >= attempts[0].started + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn backoff_does_not_hold_a_worker() {
+        let rules = Rules {
+            deadline: no_deadline,
+            retry: |job: &u32, a: &Attempt<()>| (*job == 0 && a.number == 1).then(|| Duration::from_millis(300)),
+        };
+        let done = collect(vec![0, 1], 1, rules, |_, _, _| ());
+        // Job 1 runs while job 0 backs off, so it finishes first.
+        assert_eq!(done.iter().map(|d| d.job).collect::<Vec<_>>(), [1, 0]);
+        assert_eq!(done[1].attempts.len(), 2);
+    }
+
+    #[test]
+    fn cancels_an_attempt_past_its_deadline() {
+        let rules = Rules {
+            deadline: |_: &u32| Some(Duration::from_millis(50)),
+            retry: |_: &u32, _: &Attempt<&str>| None,
+        };
+        let done = collect(vec![0], 1, rules, |_, _, cancel: &CancelToken| {
+            while !cancel.is_cancelled() {
+                thread::sleep(Duration::from_millis(5));
+            }
+            "stopped"
+        });
+        let attempt = &done[0].attempts[0];
+        assert!(attempt.timed_out);
+        assert_eq!(attempt.result, Some("stopped"));
+        assert!(attempt.elapsed < CANCEL_GRACE);
+    }
+
+    #[test]
+    fn abandons_an_attempt_that_ignores_cancellation() {
+        let rules = Rules {
+            deadline: |_: &u32| Some(Duration::from_millis(50)),
+            retry: |_: &u32, _: &Attempt<()>| None,
+        };
+        let done = collect(vec![0], 1, rules, |_, _, _| thread::sleep(CANCEL_GRACE * 2));
+        let attempt = &done[0].attempts[0];
+        assert!(attempt.timed_out);
+        assert_eq!(attempt.result, None);
+        assert!(attempt.elapsed >= CANCEL_GRACE);
+        assert!(attempt.elapsed < CANCEL_GRACE * 2);
+    }
+
+    #[test]
+    fn a_job_within_its_deadline_is_not_timed_out() {
+        let rules = Rules {
+            deadline: |_: &u32| Some(Duration::from_secs(5)),
+            retry: |_: &u32, _: &Attempt<u32>| None,
+        };
+        let done = collect(vec![4], 1, rules, |job, _, _| job + 1);
+        let attempt = &done[0].attempts[0];
+        assert!(!attempt.timed_out);
+        assert_eq!(attempt.result, Some(5));
+    }
+
+    #[test]
+    fn an_abandoned_attempt_keeps_its_worker_slot() {
+        let rules = Rules {
+            deadline: |_: &u32| Some(Duration::from_millis(50)),
+            retry: |_: &u32, _: &Attempt<()>| None,
+        };
+        let outlives_grace = CANCEL_GRACE + Duration::from_millis(500);
+        let done = collect(vec![0, 1], 1, rules, move |job, _, _| {
+            if *job == 0 {
+                thread::sleep(outlives_grace);
+            }
+        });
+        assert_eq!(done[0].attempts[0].result, None);
+        // Job 1 waits for job 0's abandoned thread to exit.
+        assert!(done[1].attempts[0].started >= outlives_grace);
+    }
+
+    #[test]
+    fn a_retry_waits_for_the_abandoned_attempt() {
+        let rules = Rules {
+            deadline: |_: &u32| Some(Duration::from_millis(50)),
+            retry: |_: &u32, a: &Attempt<()>| (a.number == 1).then_some(Duration::ZERO),
+        };
+        let outlives_grace = CANCEL_GRACE + Duration::from_millis(500);
+        let done = collect(vec![0], 2, rules, move |_, number, _| {
+            if number == 1 {
+                thread::sleep(outlives_grace);
+            }
+        });
+        let attempts = &done[0].attempts;
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts[1].started >= outlives_grace);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{policy::Policy, scheduler};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use nc_exec::{FailureClass, SandboxBackend, Schedule, TenantManifest};
+use nc_state::TenantState;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+// ===========================================================
+// 🛰️ Night Core Daemon — `nightcore serve` / `nightcore status`
+// ===========================================================
+// Tenants under modules/ run on their schedules through the same work queue,
+// verification, deadlines and retries as `run --all`. Tenants that are due
+// together run as one batch; one coming due during a batch starts after it,
+// and ticks missed meanwhile collapse into that single run.
+
+/// How often modules/ and the manifests are re-read for added, removed or
+/// rescheduled tenants.
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest idle sleep, so a distant next run does not hold up rescans.
+const MAX_IDLE: Duration = Duration::from_secs(1);
+
+/// One scheduled run, kept in the tenant's state under `run_history` and
+/// `last_run` (the proof itself is in `proof_history` as usual).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub schedule: String,
+    /// When the run was due; it starts later if a batch was still running.
+    pub due_at: String,
+    pub started_at: String,
+    pub finished_at: String,
+    /// Status of the final attempt, as in the orchestration report.
+    pub status: String,
+    pub failure: Option<FailureClass>,
+    pub sha: String,
+    pub attempts: usize,
+    pub duration_s: f64,
+}
+
+/// Reply to a `status` request on the daemon socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub pid: u32,
+    pub started_at: String,
+    pub backend: String,
+    pub parallel: usize,
+    pub tenants: Vec<TenantStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantStatus {
+    pub name: String,
+    pub schedule: Option<String>,
+    /// `policy`, `manifest` or `default`.
+    pub schedule_source: Option<String>,
+    pub next_run: Option<String>,
+    pub running: bool,
+    /// Runs and failed runs since the daemon started.
+    pub runs: u64,
+    pub failures: u64,
+    /// Survives restarts: loaded from the tenant's state on discovery.
+    pub last_run: Option<RunRecord>,
+    /// Why the tenant cannot be scheduled (e.g. an invalid manifest).
+    pub error: Option<String>,
+}
+
+struct Tenant {
+    dir: PathBuf,
+    schedule: Option<(Schedule, &'static str)>,
+    next: Option<DateTime<Utc>>,
+    status: TenantStatus,
+}
+
+/// 🛰️ Run every scheduled tenant until the process is stopped, answering
+/// `nightcore status` on `socket`.
+pub fn serve(sandbox: Arc<dyn SandboxBackend + Send + Sync>, policy: Policy, parallel: usize, socket: &Path) -> Result<()> {
+    let listener = bind(socket)?;
+    let status = Arc::new(Mutex::new(DaemonStatus {
+        pid: std::process::id(),
+        started_at: timestamp(Utc::now()),
+        backend: sandbox.name().into(),
+        parallel,
+        tenants: vec![],
+    }));
+    {
+        let status = Arc::clone(&status);
+        thread::spawn(move || answer_status(listener, &status));
+    }
+    println!("🛰️  Night Core daemon up (pid {}, backend {}, parallel {}) — status on {}",
+        std::process::id(), sandbox.name(), parallel, socket.display());
+
+    let modules_dir = PathBuf::from("modules");
+    let mut tenants: BTreeMap<String, Tenant> = BTreeMap::new();
+    let mut last_scan: Option<Instant> = None;
+    loop {
+        if last_scan.is_none_or(|t| t.elapsed() >= RESCAN_INTERVAL) {
+            rescan(&modules_dir, &policy, &mut tenants);
+            last_scan = Some(Instant::now());
+            publish(&status, &tenants);
+        }
+
+        let now = Utc::now();
+        let due: Vec<PathBuf> = tenants.values()
+            .filter(|t| t.next.is_some_and(|next| next <= now))
+            .map(|t| t.dir.clone())
+            .collect();
+        if due.is_empty() {
+            let until_next = tenants.values()
+                .filter_map(|t| t.next)
+                .min()
+                .and_then(|next| (next - now).to_std().ok())
+                .unwrap_or(MAX_IDLE);
+            thread::sleep(until_next.min(MAX_IDLE));
+            continue;
+        }
+        run_due(due, &sandbox, &policy, parallel, &mut tenants, &status);
+    }
+}
+
+/// Run one batch of due tenants and record each run as it finishes.
+fn run_due(
+    due: Vec<PathBuf>,
+    sandbox: &Arc<dyn SandboxBackend + Send + Sync>,
+    policy: &Policy,
+    parallel: usize,
+    tenants: &mut BTreeMap<String, Tenant>,
+    status: &Mutex<DaemonStatus>,
+) {
+    let mut due_at = BTreeMap::new();
+    for dir in &due {
+        if let Some(tenant) = tenants.get_mut(&tenant_name(dir)) {
+            due_at.insert(dir.clone(), tenant.next);
+            tenant.status.running = true;
+        }
+    }
+    publish(status, tenants);
+    println!("⏰ {} tenant(s) due: {}", due.len(),
+        due.iter().map(|d| tenant_name(d)).collect::<Vec<_>>().join(", "));
+
+    let batch_start = Utc::now();
+    let job = crate::tenant_job(sandbox, policy, false);
+    scheduler::run_queue(due, parallel, crate::tenant_rules(policy), job, |done| {
+        let name = tenant_name(&done.job);
+        let Some(tenant) = tenants.get_mut(&name) else { return };
+        let first = &done.attempts[0];
+        let last = done.attempts.last().unwrap();
+        let record = RunRecord {
+            schedule: tenant.status.schedule.clone().unwrap_or_default(),
+            due_at: due_at.get(&done.job).copied().flatten().map(|t| t.to_rfc3339()).unwrap_or_default(),
+            started_at: (batch_start + chrono::Duration::from_std(first.started).unwrap_or_default()).to_rfc3339(),
+            finished_at: Utc::now().to_rfc3339(),
+            status: crate::attempt_status(last),
+            failure: crate::attempt_failure(last),
+            sha: match &last.result {
+                Some(Ok(run)) => run.sha.clone(),
+                _ => "<none>".into(),
+            },
+            attempts: done.attempts.len(),
+            duration_s: done.attempts.iter().map(|a| a.elapsed.as_secs_f64()).sum(),
+        };
+        if let Err(e) = persist(&name, &record) {
+            eprintln!("⚠️  {}: run record not persisted: {:#}", name, e);
+        }
+
+        tenant.next = tenant.schedule.as_ref().and_then(|(s, _)| s.next_after(Utc::now()));
+        let st = &mut tenant.status;
+        st.running = false;
+        st.runs += 1;
+        if record.status != "ok" {
+            st.failures += 1;
+        }
+        st.next_run = tenant.next.map(timestamp);
+        println!("{:<20} {:<40} ⏱️  {:.2}s, next run {}",
+            name, record.status, record.duration_s, st.next_run.as_deref().unwrap_or("never"));
+        st.last_run = Some(record);
+        publish(status, tenants);
+    });
+}
+
+/// Sync `tenants` with modules/: pick up new tenants and schedule changes,
+/// drop removed ones. A tenant keeps its next run while its schedule stands.
+fn rescan(modules_dir: &Path, policy: &Policy, tenants: &mut BTreeMap<String, Tenant>) {
+    let mut dirs: Vec<PathBuf> = match fs::read_dir(modules_dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).filter(|p| p.is_dir()).collect(),
+        Err(e) => {
+            eprintln!("⚠️  Cannot read {}: {}", modules_dir.display(), e);
+            return;
+        }
+    };
+    dirs.sort();
+
+    let mut seen = BTreeSet::new();
+    for dir in dirs {
+        let name = tenant_name(&dir);
+        seen.insert(name.clone());
+        let (schedule, error) = tenant_schedule(&dir, &name, policy);
+        let tenant = tenants.entry(name.clone()).or_insert_with(|| Tenant {
+            dir: dir.clone(),
+            schedule: None,
+            next: None,
+            status: TenantStatus {
+                name: name.clone(),
+                schedule: None,
+                schedule_source: None,
+                next_run: None,
+                running: false,
+                runs: 0,
+                failures: 0,
+                last_run: last_run(&name),
+                error: None,
+            },
+        });
+        if let Some(e) = &error {
+            if tenant.status.error.as_ref() != Some(e) {
+                eprintln!("⚠️  {} not scheduled: {}", name, e);
+            }
+        }
+        tenant.status.error = error;
+
+        let key = |s: &Option<(Schedule, &'static str)>| s.as_ref().map(|(s, src)| (s.as_str().to_string(), *src));
+        if key(&tenant.schedule) == key(&schedule) {
+            continue;
+        }
+        tenant.next = schedule.as_ref().and_then(|(s, _)| s.next_after(Utc::now()));
+        tenant.status.schedule = schedule.as_ref().map(|(s, _)| s.to_string());
+        tenant.status.schedule_source = schedule.as_ref().map(|(_, src)| src.to_string());
+        tenant.status.next_run = tenant.next.map(timestamp);
+        match &schedule {
+            Some((s, src)) => println!("📅 {}: '{}' ({}), next run {}",
+                name, s, src, tenant.status.next_run.as_deref().unwrap_or("never")),
+            None if tenant.status.error.is_none() => println!("💤 {}: no schedule", name),
+            None => {}
+        }
+        tenant.schedule = schedule;
+    }
+    tenants.retain(|name, _| {
+        let keep = seen.contains(name);
+        if !keep {
+            println!("🗑️  {}: removed from modules/, unscheduled", name);
+        }
+        keep
+    });
+}
+
+/// A tenant's schedule and where it came from: the policy's
+/// `[serve.schedules]`, else its manifest, else the policy default.
+fn tenant_schedule(dir: &Path, name: &str, policy: &Policy) -> (Option<(Schedule, &'static str)>, Option<String>) {
+    if let Some(schedule) = policy.serve.schedules.get(name) {
+        return (Some((schedule.clone(), "policy")), None);
+    }
+    match TenantManifest::load(dir) {
+        Ok(manifest) => {
+            let schedule = manifest.schedule.map(|s| (s, "manifest"))
+                .or_else(|| policy.serve.default_schedule.clone().map(|s| (s, "default")));
+            (schedule, None)
+        }
+        Err(e) => (None, Some(format!("{:#}", e))),
+    }
+}
+
+fn last_run(tenant: &str) -> Option<RunRecord> {
+    TenantState::open(".", tenant).ok()?.get_json("last_run").ok()?
+}
+
+fn persist(tenant: &str, record: &RunRecord) -> Result<()> {
+    let state = TenantState::open(".", tenant)?;
+    state.append_json("run_history", record)?;
+    state.put_json("last_run", record)
+}
+
+fn publish(status: &Mutex<DaemonStatus>, tenants: &BTreeMap<String, Tenant>) {
+    status.lock().unwrap().tenants = tenants.values().map(|t| t.status.clone()).collect();
+}
+
+fn tenant_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Bind the status socket, replacing one left behind by a daemon that is no
+/// longer running.
+fn bind(path: &Path) -> Result<UnixListener> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(anyhow!("a daemon is already listening on {}", path.display()));
+        }
+        fs::remove_file(path).with_context(|| format!("removing stale socket {}", path.display()))?;
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    UnixListener::bind(path).with_context(|| format!("binding status socket {}", path.display()))
+}
+
+/// Line protocol: the client sends `status`, the daemon replies with one line
+/// of JSON and closes the connection.
+fn answer_status(listener: UnixListener, status: &Mutex<DaemonStatus>) {
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+        let mut request = String::new();
+        let _ = BufReader::new(&stream).read_line(&mut request);
+        let reply = match request.trim() {
+            "status" => serde_json::to_string(&*status.lock().unwrap()).unwrap_or_default(),
+            other => serde_json::json!({ "error": format!("unknown request '{}'", other) }).to_string(),
+        };
+        let _ = writeln!(stream, "{}", reply);
+    }
+}
+
+/// 📡 Ask the daemon listening on `socket` for its status.
+pub fn query_status(socket: &Path) -> Result<DaemonStatus> {
+    let mut stream = UnixStream::connect(socket)
+        .with_context(|| format!("no daemon listening on {} (start one with `nightcore serve`)", socket.display()))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all(b"status\n")?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    serde_json::from_str(&reply).with_context(|| format!("unexpected reply from daemon: {}", reply.trim()))
+}
+
+pub fn print_status(status: &DaemonStatus) {
+    println!("🛰️  Night Core daemon — pid {}, up since {}, backend {}, parallel {}",
+        status.pid, status.started_at, status.backend, status.parallel);
+    println!("{:<20} {:<18} {:<9} {:<21} {:<30} {:>5} {:>5}",
+        "TENANT", "SCHEDULE", "SOURCE", "NEXT RUN", "LAST RUN", "RUNS", "FAIL");
+    for t in &status.tenants {
+        let next = if t.running { "running".to_string() } else { t.next_run.clone().unwrap_or_else(|| "-".into()) };
+        let last = t.last_run.as_ref()
+            .map(|r| format!("{} ({} att.)", r.status, r.attempts))
+            .unwrap_or_else(|| "-".into());
+        println!("{:<20} {:<18} {:<9} {:<21} {:<30} {:>5} {:>5}",
+            t.name,
+            t.schedule.as_deref().unwrap_or("-"),
+            t.schedule_source.as_deref().unwrap_or("-"),
+            next, last, t.runs, t.failures);
+        if let Some(e) = &t.error {
+            println!("    ⚠️  {}", e);
+        }
+    }
+}
@@ -1,25 +1,67 @@
 use anyhow::{Context, Result, anyhow};
 use base64::{engine::general_purpose::STANDARD, Engine as _};
-use ed25519_dalek::{Signature, SigningKey, VerifyingKey, Verifier};
+use ed25519_dalek::SigningKey;
 use sha2::{Digest, Sha256};
 use std::{
     fs,
-    path::Path,
+    path::{Component, Path, PathBuf},
     convert::TryInto,
     fs::OpenOptions,
     io::Write,
     process::Command,
-    sync::Once,
+    sync::{Arc, Once},
     io::Read,
+    time::{Duration, Instant},
 };
 use chrono::{Local, Utc};
+use nc_exec::{
+    analyze, BatchRecord, BatchWriter, CancelToken, Capabilities, ExecConfig, ExecOutcome, ExecProof, FailureClass,
+    KvLimits, ModuleChanged, Mount, OutputCapture, RetryPolicy, SandboxBackend, TenantManifest, WasmType,
+};
 use nc_state::TenantState; // ✅ persistent sled state per tenant
+use crate::policy::Policy;
 
 static INIT_LOG: Once = Once::new();
 
-/// ===========================================================
-/// Night Core™ v38-Pro — Verify + Proof + Persistent Audit
-/// ===========================================================
+// ===========================================================
+// Night Core™ v38-Pro — Verify + Proof + Persistent Audit
+// ===========================================================
+
+/// Result of a verified tenant run, as seen by the orchestrator.
+pub struct RunOutcome {
+    pub sha: String,
+    pub exec: ExecProof,
+    pub exec_duration_s: f64,
+}
+
+/// A call to a manifest-declared export in place of the tenant entrypoint;
+/// `args` are parsed against the declared parameter types.
+pub struct Invoke {
+    pub export: String,
+    pub args: Vec<String>,
+}
+
+/// Error returned by [`verify_and_run`] for a run that got as far as its
+/// config, tagged with the stage it failed at for the retry policy.
+#[derive(Debug)]
+pub struct RunFailure {
+    pub class: FailureClass,
+    message: String,
+}
+
+impl std::fmt::Display for RunFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RunFailure {}
+
+/// Failure class of a [`verify_and_run`] error; untagged errors come from
+/// reading the module or opening tenant state and count as `io`.
+pub fn failure_class(e: &anyhow::Error) -> FailureClass {
+    e.downcast_ref::<RunFailure>().map(|f| f.class).unwrap_or(FailureClass::Io)
+}
 
 /// 🔍 Environment verification
 pub fn verify_environment() -> Result<()> {
@@ -66,11 +108,20 @@ pub fn ensure_pubkey_sync(tenant_dir: &str, tenant_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// ✅ Verify Ed25519 signature + SHA-256 integrity + persistent proof log
-pub fn verify_and_run(dir: &Path, proof: bool) -> Result<String> {
+/// ✅ Verify Ed25519 signature + SHA-256 integrity, execute through the
+/// sandbox backend, and persist the combined proof record. Once `cancel` is
+/// set the run is not started, or is stopped by the backend. `attempt`
+/// (1-based) is recorded so retried runs can be told apart in the history.
+pub fn verify_and_run(
+    dir: &Path,
+    proof: bool,
+    backend: &dyn SandboxBackend,
+    policy: &Policy,
+    invoke: Option<&Invoke>,
+    cancel: &CancelToken,
+    attempt: u32,
+) -> Result<RunOutcome> {
     let module_path = dir.join("module.wasm");
-    let sig_path = dir.join("module.sig");
-    let pub_path = dir.join("pubkey.b64");
 
     let module_bytes = fs::read(&module_path)
         .with_context(|| format!("reading {:?}", module_path))?;
@@ -82,31 +133,18 @@ pub fn verify_and_run(dir: &Path, proof: bool) -> Result<String> {
     // ✅ Persistent sled state
     let state = TenantState::open(".", &tenant_name)?;
 
-    // Decode signature
-    let sig_bytes_vec = STANDARD
-        .decode(fs::read_to_string(&sig_path)?.trim())
-        .context("decoding signature file")?;
-    let sig_bytes: [u8; 64] = sig_bytes_vec.clone()
-        .try_into()
-        .map_err(|_| anyhow!("invalid signature length: {}", sig_bytes_vec.len()))?;
-    let sig = Signature::from_bytes(&sig_bytes);
-
-    // Decode public key
-    let pub_bytes_vec = STANDARD
-        .decode(fs::read_to_string(&pub_path)?.trim())
-        .context("decoding pubkey file")?;
-    let pub_bytes: [u8; 32] = pub_bytes_vec.clone()
-        .try_into()
-        .map_err(|_| anyhow!("invalid pubkey length: {}", pub_bytes_vec.len()))?;
-    let vk = VerifyingKey::from_bytes(&pub_bytes)
-        .with_context(|| "invalid verifying key")?;
-
-    // Verify signature and record outcome
+    // Verify signature and imports (against the capabilities the run would
+    // get) through the backend, which gates its artifact cache on this check,
+    // and record outcome
     let sha_hex = format!("{:X}", Sha256::digest(&module_bytes));
     let mut verified = false;
 
-    let result = vk.verify(&module_bytes, &sig);
-    match result {
+    // A manifest (or call) that cannot be turned into a config is neither a
+    // verification nor an execution event.
+    let (mut cfg, manifest) = exec_config(dir, &tenant_name, &module_path, backend, policy, &state, invoke)
+        .map_err(|e| e.context(RunFailure { class: FailureClass::Config, message: format!("cannot run {}", tenant_name) }))?;
+    cfg.cancel = cancel.clone();
+    match verify_pinned(backend, &module_path, &mut cfg, &sha_hex) {
         Ok(_) => {
             verified = true;
             println!("✅ VERIFIED: {}", dir.display());
@@ -115,16 +153,52 @@ pub fn verify_and_run(dir: &Path, proof: bool) -> Result<String> {
             println!("  • Size: {} bytes", module_bytes.len());
         }
         Err(e) => {
-            eprintln!("❌ Verification FAILED for {}: {}", dir.display(), e);
+            eprintln!("❌ Verification FAILED for {}: {:#}", dir.display(), e);
         }
     }
 
+    // 🚀 Only verified modules ever reach the sandbox
+    let mut exec_duration_s = 0.0;
+    let (exec_result, failure) = if verified && cancel.is_cancelled() {
+        eprintln!("⏱️  {}: deadline passed during verification, not executed", tenant_name);
+        (Some(Err(anyhow!("cancelled before execution (orchestration deadline)"))), Some(FailureClass::Timeout))
+    } else if verified {
+        let t0 = Instant::now();
+        let (res, failure) = match backend.execute(&cfg) {
+            Ok(proof) => match check_invocation(&manifest, &proof) {
+                Ok(()) => {
+                    let failure = FailureClass::of_outcome(&proof.outcome);
+                    (Ok(proof), failure)
+                }
+                Err(e) => (Err(e), Some(FailureClass::Config)),
+            },
+            Err(e) if e.is::<ModuleChanged>() => (Err(e), Some(FailureClass::Verification)),
+            Err(e) => (Err(e), Some(FailureClass::Backend)),
+        };
+        exec_duration_s = t0.elapsed().as_secs_f64();
+        match &res {
+            Ok(p) => println!("  • Execution: {} via {} ({:.2}s)", p.status, p.backend, exec_duration_s),
+            Err(e) => eprintln!("❌ Execution FAILED for {}: {:#}", dir.display(), e),
+        }
+        (Some(res), failure)
+    } else {
+        (None, Some(FailureClass::Verification))
+    };
+
+    let execution = match &exec_result {
+        Some(res) => execution_record(res, backend, exec_duration_s),
+        None => serde_json::Value::Null,
+    };
+
     // 🪶 Persist audit record for both success & failure
     let record = serde_json::json!({
         "sha256": sha_hex,
         "size": module_bytes.len(),
         "verified": verified,
         "timestamp": Utc::now().to_rfc3339(),
+        "attempt": attempt,
+        "failure": failure,
+        "execution": execution,
     });
     state.put_json("last_proof", &record)?;
     state.append_json("proof_history", &record)?;
@@ -135,10 +209,376 @@ pub fn verify_and_run(dir: &Path, proof: bool) -> Result<String> {
         write_proof_report(dir, &sha_hex, module_bytes.len())?;
     }
 
-    if verified {
-        Ok(sha_hex)
-    } else {
-        Err(anyhow!("verification failed for {}", tenant_name))
+    match exec_result {
+        Some(Ok(exec)) => Ok(RunOutcome { sha: sha_hex, exec, exec_duration_s }),
+        Some(Err(e)) => Err(e.context(RunFailure {
+            class: failure.unwrap_or(FailureClass::Backend),
+            message: format!("execution failed for {}", tenant_name),
+        })),
+        None => Err(RunFailure {
+            class: FailureClass::Verification,
+            message: format!("verification failed for {}", tenant_name),
+        }.into()),
+    }
+}
+
+/// 🔎 Re-verify a tenant (signature, hash, and imports against its
+/// policy-capped capabilities) without running it; returns the module's
+/// SHA-256. Errors are tagged like [`verify_and_run`]'s.
+pub fn verify_tenant(dir: &Path, backend: &dyn SandboxBackend, policy: &Policy) -> Result<String> {
+    let module_path = dir.join("module.wasm");
+    let tenant_name = dir.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string());
+    let state = TenantState::open(".", &tenant_name)?;
+
+    let (cfg, _) = exec_config(dir, &tenant_name, &module_path, backend, policy, &state, None)
+        .map_err(|e| e.context(RunFailure { class: FailureClass::Config, message: format!("cannot verify {}", tenant_name) }))?;
+    let sha = backend.verify(&module_path, &cfg.capabilities)
+        .map_err(|e| e.context(RunFailure { class: FailureClass::Verification, message: format!("verification failed for {}", tenant_name) }))?;
+    Ok(sha.to_uppercase())
+}
+
+/// Verify through the backend and pin `cfg` to the verified digest, which
+/// must also be `sha_hex`, the digest of the bytes the caller records.
+fn verify_pinned(backend: &dyn SandboxBackend, module_path: &Path, cfg: &mut ExecConfig, sha_hex: &str) -> Result<()> {
+    let sha = backend.verify(module_path, &cfg.capabilities)?;
+    cfg.verified_sha256 = Some(sha);
+    cfg.check_verified(sha_hex)
+}
+
+/// Execution part of a `proof_history` entry.
+fn execution_record(res: &Result<ExecProof>, backend: &dyn SandboxBackend, exec_duration_s: f64) -> serde_json::Value {
+    match res {
+        Ok(p) => serde_json::json!({
+            "backend": p.backend,
+            "abi": p.abi,
+            "status": p.status,
+            "outcome": p.outcome,
+            "duration_ms": (exec_duration_s * 1000.0) as u64,
+            "fuel_consumed": p.fuel_consumed,
+            "peak_memory_bytes": p.peak_memory_bytes,
+            "stdin_sha256": p.stdin_sha256,
+            "stdout_sha256": p.stdout_sha256,
+            "stderr_sha256": p.stderr_sha256,
+            "output_truncated": p.output_truncated,
+            "invocation": p.invocation,
+            "determinism": p.determinism,
+            "host_api": p.host_api,
+            "compiler": p.compiler,
+            "engine_fingerprint": p.engine_fingerprint,
+            "started_at": p.started_at,
+            "finished_at": p.finished_at,
+        }),
+        Err(e) => serde_json::json!({
+            "backend": backend.name(),
+            "status": format!("error: {:#}", e),
+            "duration_ms": (exec_duration_s * 1000.0) as u64,
+        }),
+    }
+}
+
+/// Proof fields a deterministic re-execution must reproduce exactly.
+const REPLAY_FIELDS: &[&str] = &["determinism", "outcome", "fuel_consumed", "stdout_sha256", "stderr_sha256"];
+
+/// 🔁 Re-execute a deterministic tenant and compare the new proof with a
+/// recorded one: a `proof_report.jsonl` from another node (`against`), or
+/// this node's `last_proof` when none is given.
+pub fn replay(dir: &Path, backend: &dyn SandboxBackend, policy: &Policy, against: Option<&Path>) -> Result<()> {
+    let tenant_name = dir.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Read the reference before the new run overwrites last_proof.
+    let (expected, expected_sha) = match against {
+        Some(path) => {
+            let raw = fs::read_to_string(path)
+                .with_context(|| format!("reading {}", path.display()))?;
+            let line = raw.lines().rev().find(|l| !l.trim().is_empty())
+                .ok_or_else(|| anyhow!("{} holds no proof", path.display()))?;
+            let proof: serde_json::Value = serde_json::from_str(line)
+                .with_context(|| format!("parsing proof in {}", path.display()))?;
+            let sha = proof["module_sha256"].as_str().unwrap_or_default().to_string();
+            (proof, sha)
+        }
+        None => {
+            let state = TenantState::open(".", &tenant_name)?;
+            let record: serde_json::Value = state.get_json("last_proof")?
+                .ok_or_else(|| anyhow!("no recorded proof for {}", tenant_name))?;
+            let sha = record["sha256"].as_str().unwrap_or_default().to_string();
+            (record["execution"].clone(), sha)
+        }
+    };
+    if expected["determinism"].is_null() {
+        return Err(anyhow!("recorded run of {} was not deterministic; nothing to compare", tenant_name));
+    }
+
+    let run = verify_and_run(dir, false, backend, policy, None, &CancelToken::default(), 1)?;
+    if !run.sha.eq_ignore_ascii_case(&expected_sha) {
+        return Err(anyhow!("module changed since the recorded run ({} vs {})", run.sha, expected_sha));
+    }
+    let actual = serde_json::to_value(&run.exec)?;
+
+    println!("🔁 Replay of {} against {}", tenant_name,
+        against.map(|p| p.display().to_string()).unwrap_or_else(|| "last_proof".into()));
+    let mut mismatches = 0;
+    for field in REPLAY_FIELDS {
+        if expected[field] == actual[field] {
+            println!("  • {}: match", field);
+        } else {
+            mismatches += 1;
+            println!("  ❌ {}: recorded {}, replayed {}", field, expected[field], actual[field]);
+        }
+    }
+    if mismatches > 0 {
+        return Err(anyhow!("replay of {} diverged in {} field(s)", tenant_name, mismatches));
+    }
+    println!("✅ {} reproduced its recorded proof", tenant_name);
+    Ok(())
+}
+
+/// Totals of a request/response batch.
+pub struct BatchSummary {
+    pub sha: String,
+    pub records: usize,
+    pub ok: usize,
+}
+
+/// 📦 Request/response mode: verify once, then run the module once per
+/// payload (fed on stdin). Each run gets its own `proof_history` entry and a
+/// line in `results` pointing back at it.
+pub fn verify_and_run_batch(
+    dir: &Path,
+    backend: &dyn SandboxBackend,
+    policy: &Policy,
+    payloads: &[Vec<u8>],
+    results: &Path,
+) -> Result<BatchSummary> {
+    let module_path = dir.join("module.wasm");
+    let module_bytes = fs::read(&module_path)
+        .with_context(|| format!("reading {:?}", module_path))?;
+    let tenant_name = dir.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "unknown".to_string());
+    let state = TenantState::open(".", &tenant_name)?;
+    let sha_hex = format!("{:X}", Sha256::digest(&module_bytes));
+
+    let (mut cfg, _) = exec_config(dir, &tenant_name, &module_path, backend, policy, &state, None)?;
+    if let Err(e) = verify_pinned(backend, &module_path, &mut cfg, &sha_hex) {
+        eprintln!("❌ Verification FAILED for {}: {:#}", dir.display(), e);
+        let record = serde_json::json!({
+            "sha256": sha_hex,
+            "size": module_bytes.len(),
+            "verified": false,
+            "timestamp": Utc::now().to_rfc3339(),
+            "execution": serde_json::Value::Null,
+        });
+        state.put_json("last_proof", &record)?;
+        state.append_json("proof_history", &record)?;
+        return Err(anyhow!("verification failed for {}", tenant_name));
+    }
+    println!("✅ VERIFIED: {}", dir.display());
+    println!("  • SHA-256: {}", sha_hex);
+
+    // stdout is the result; echoing it per record would just be noise.
+    cfg.output.echo = false;
+
+    let mut out = BatchWriter::create(results)?;
+    let mut ok = 0;
+    for (index, payload) in payloads.iter().enumerate() {
+        cfg.stdin = Some(payload.clone());
+        let t0 = Instant::now();
+        let res = backend.execute(&cfg);
+        let exec_duration_s = t0.elapsed().as_secs_f64();
+
+        let mut execution = execution_record(&res, backend, exec_duration_s);
+        execution["batch"] = serde_json::json!({ "index": index, "results": results });
+        let record = serde_json::json!({
+            "sha256": sha_hex,
+            "size": module_bytes.len(),
+            "verified": true,
+            "timestamp": Utc::now().to_rfc3339(),
+            "execution": execution,
+        });
+        state.put_json("last_proof", &record)?;
+        let proof_index = state.append_json("proof_history", &record)?;
+
+        let mut line = BatchRecord::new(index, payload, &res, (exec_duration_s * 1000.0) as u64);
+        line.proof_index = Some(proof_index);
+        if line.status == "ok" {
+            ok += 1;
+        }
+        println!("  • Record {}: {} ({} ms)", index, line.status, line.duration_ms);
+        out.write(&line)?;
+    }
+    println!("🪶 {} batch record(s) recorded for tenant {}", payloads.len(), tenant_name);
+
+    Ok(BatchSummary { sha: sha_hex, records: payloads.len(), ok })
+}
+
+/// Slack on top of a manifest `timeout_ms` (which bounds the guest alone) for
+/// verification, compilation and proof writing.
+const DEADLINE_SLACK: Duration = Duration::from_secs(2);
+
+/// Orchestration deadline for one tenant run: its manifest `timeout_ms` plus
+/// [`DEADLINE_SLACK`], else the policy's `timeout_seconds` (0 = none).
+pub fn orchestration_deadline(dir: &Path, policy: &Policy) -> Option<Duration> {
+    let manifest_ms = TenantManifest::load(dir).ok().and_then(|m| m.limits.timeout_ms);
+    match (manifest_ms, policy.orchestration.timeout_seconds) {
+        (Some(ms), _) => Some(Duration::from_millis(ms) + DEADLINE_SLACK),
+        (None, 0) => None,
+        (None, secs) => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Retry policy for one tenant: its manifest `retry` over the policy's
+/// `[retry]` section. An unreadable manifest gets the policy as is; the run
+/// itself reports why.
+pub fn retry_policy(dir: &Path, policy: &Policy) -> RetryPolicy {
+    match TenantManifest::load(dir).ok().and_then(|m| m.retry) {
+        Some(retry) => retry.over(&policy.retry),
+        None => policy.retry.clone(),
+    }
+}
+
+/// Build the sandbox config for a verified tenant from its typed manifest,
+/// capped by the host policy (limits, output caps, permission switches).
+fn exec_config(
+    dir: &Path,
+    tenant_name: &str,
+    module_path: &Path,
+    backend: &dyn SandboxBackend,
+    policy: &Policy,
+    state: &TenantState,
+    invoke: Option<&Invoke>,
+) -> Result<(ExecConfig, TenantManifest)> {
+    let manifest = TenantManifest::load(dir)?;
+    if let Some(pinned) = &manifest.backend {
+        if pinned != backend.name() {
+            return Err(anyhow!(
+                "{} requires backend '{}', selected '{}'", tenant_name, pinned, backend.name()
+            ));
+        }
+    }
+
+    let mut cfg = manifest.exec_config(tenant_name, module_path)?;
+    cfg.compiler = cfg.compiler.or(policy.compiler);
+    let problems = cfg.compiler.problems(cfg.deterministic.is_some());
+    if !problems.is_empty() {
+        return Err(anyhow!("compiler profile of {} (with policy defaults): {}", tenant_name, problems.join("; ")));
+    }
+    if let Some(invoke) = invoke {
+        cfg.call_args = manifest.invocation_args(&invoke.export, &invoke.args)?;
+        cfg.entrypoint = invoke.export.clone();
+    }
+    restrict_capabilities(&mut cfg.capabilities, policy);
+    confine_mounts(&cfg.mounts, &policy.permissions.mount_roots)
+        .with_context(|| format!("mounts of {}", tenant_name))?;
+    if cfg.capabilities.mount_access("/scratch").is_some() {
+        // Created up front: a read-only grant would otherwise preopen a missing dir.
+        let scratch = Path::new("state").join(tenant_name).join("scratch");
+        fs::create_dir_all(&scratch).with_context(|| format!("creating {}", scratch.display()))?;
+        cfg.mounts.push(Mount::read_write(scratch, "/scratch"));
+    }
+
+    if cfg.capabilities.kv {
+        cfg.kv = Some(Arc::new(state.clone()));
+        cfg.kv_limits = KvLimits {
+            max_key_bytes: policy.kv.max_key_bytes,
+            max_value_bytes: policy.kv.max_value_bytes,
+            max_total_bytes: policy.kv.max_total_bytes,
+        };
+    }
+
+    if cfg.inherit_env && !policy.permissions.allow_host_env {
+        eprintln!("⚠️  {} requested inherit_env — denied by policy (allow_host_env = false)", tenant_name);
+        cfg.inherit_env = false;
+    }
+
+    // Manifest may tighten or relax the policy-wide store limits.
+    let limits = &mut cfg.limits;
+    limits.max_memory_mb = limits.max_memory_mb.or(Some(policy.orchestration.max_memory_mb));
+    limits.max_table_elements = limits.max_table_elements.or(Some(policy.orchestration.max_table_elements));
+    limits.max_instances = limits.max_instances.or(Some(policy.orchestration.max_instances));
+
+    cfg.output = OutputCapture {
+        max_stdout_bytes: policy.output.max_stdout_bytes,
+        max_stderr_bytes: policy.output.max_stderr_bytes,
+        echo: policy.output.echo,
+    };
+    Ok((cfg, manifest))
+}
+
+/// A completed call must hand back what the manifest promised; a trapped or
+/// exited call has no results to check.
+fn check_invocation(manifest: &TenantManifest, proof: &ExecProof) -> Result<()> {
+    let Some(call) = &proof.invocation else { return Ok(()) };
+    if proof.outcome != (ExecOutcome::Exited { code: 0 }) {
+        return Ok(());
+    }
+    let declared = manifest.export(&call.export)
+        .ok_or_else(|| anyhow!("'{}' is not a declared export of {}", call.export, manifest.name))?;
+    let got: Vec<WasmType> = call.results.iter().map(|v| v.ty()).collect();
+    if got != declared.results {
+        return Err(anyhow!(
+            "'{}' returned ({}) but the manifest declares {}",
+            call.export,
+            got.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "),
+            declared
+        ));
+    }
+    println!(
+        "  • Result: {}({}) = [{}]",
+        call.export,
+        call.args.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "),
+        call.results.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+    );
+    Ok(())
+}
+
+/// Reject manifest mounts whose host directory is not inside one of the
+/// policy's `mount_roots`, whether through `..`, an absolute path or a symlink.
+fn confine_mounts(mounts: &[Mount], roots: &[PathBuf]) -> Result<()> {
+    let roots = roots.iter().filter_map(|r| r.canonicalize().ok()).collect::<Vec<_>>();
+    for m in mounts {
+        if m.host_path.components().any(|c| c == Component::ParentDir) {
+            return Err(anyhow!("{} → {}: host path may not contain '..'", m.host_path.display(), m.guest_path));
+        }
+        let resolved = resolve_host_path(&m.host_path)?;
+        if !roots.iter().any(|root| resolved.starts_with(root)) {
+            return Err(anyhow!(
+                "{} → {}: host path is outside the policy's mount_roots", m.host_path.display(), m.guest_path
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Canonical form of `path`; a path not created yet (writable mounts are made
+/// on demand) is resolved through its nearest existing ancestor.
+fn resolve_host_path(path: &Path) -> Result<PathBuf> {
+    let absolute = std::env::current_dir()?.join(path);
+    let mut existing = absolute.as_path();
+    let mut missing = vec![];
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            return Ok(missing.iter().rev().fold(resolved, |p, name| p.join(name)));
+        }
+        missing.extend(existing.file_name());
+        existing = existing.parent().ok_or_else(|| anyhow!("cannot resolve {}", path.display()))?;
+    }
+}
+
+/// Drop manifest grants the policy switches off. Every capability is named
+/// so a new one has to be given a policy decision here.
+fn restrict_capabilities(caps: &mut Capabilities, policy: &Policy) {
+    let Capabilities { stdout, net, kv, fs_read, fs_write, stderr: _, env: _, clock: _, random: _, log: _ } = caps;
+    *stdout &= policy.permissions.allow_stdout;
+    *net &= policy.permissions.allow_net;
+    *kv &= policy.permissions.allow_kv;
+    if !policy.permissions.allow_fs_read {
+        fs_read.clear();
+        fs_write.clear();
     }
 }
 
@@ -172,7 +612,6 @@ pub fn write_proof_report(tenant_path: &Path, sha_hex: &str, module_size: usize)
 
     let mut file = OpenOptions::new()
         .create(true)
-        .write(true)
         .truncate(false)
         .append(true)
         .open(&log_file)
@@ -210,10 +649,48 @@ pub fn write_proof_report(tenant_path: &Path, sha_hex: &str, module_size: usize)
     Ok(())
 }
 
+/// 🔬 Print the static module analysis and fail, as verification would, on
+/// imports the (policy-capped) capabilities do not cover.
+fn inspect_module(module_path: &Path, caps: &Capabilities) -> Result<()> {
+    let bytes = fs::read(module_path)?;
+    let report = analyze(&bytes).with_context(|| format!("analysing {}", module_path.display()))?;
+    let denied = report.denied_imports(caps);
+
+    println!("\n🔬 Module analysis ({}, {} bytes)", report.kind, bytes.len());
+    for import in &report.imports {
+        match denied.iter().find(|d| d.import.module == import.module && d.import.name == import.name) {
+            Some(d) => println!("  🚫 Import: {} — {}", import, d.reason),
+            None => println!("  • Import: {}", import),
+        }
+    }
+    for export in &report.exports {
+        println!("  • Export: {} ({})", export.name, export.kind);
+    }
+    for m in &report.memories {
+        let max = m.maximum_pages.map(|p| p.to_string()).unwrap_or_else(|| "∞".into());
+        println!(
+            "  • Memory: {}..{} pages{}{}",
+            m.initial_pages, max,
+            if m.shared { ", shared" } else { "" },
+            if m.memory64 { ", 64-bit" } else { "" }
+        );
+    }
+    for s in &report.custom_sections {
+        println!("  • Custom section: {} ({} bytes)", s.name, s.size);
+    }
+
+    if !denied.is_empty() {
+        eprintln!("❌ {} import(s) would fail verification", denied.len());
+        return Err(anyhow!("disallowed imports in {}", module_path.display()));
+    }
+    println!("✅ All imports allowed");
+    Ok(())
+}
+
 /// ===========================================================
 /// 📄 Manifest Inspector (Restored for CLI Compatibility)
 /// ===========================================================
-pub fn inspect_manifest(dir: &Path) -> Result<()> {
+pub fn inspect_manifest(dir: &Path, policy: &Policy) -> Result<()> {
     let manifest_path = dir.join("manifest.json");
 
     if !manifest_path.exists() {
@@ -230,5 +707,48 @@ pub fn inspect_manifest(dir: &Path) -> Result<()> {
     println!("{}", contents);
     println!("------------------------------\n");
 
+    let manifest = match TenantManifest::parse(&contents) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("❌ Manifest invalid for {}: {:#}", dir.display(), e);
+            return Err(anyhow!("invalid manifest in {}", dir.display()));
+        }
+    };
+    println!("✅ Manifest valid (schema {})", manifest.schema);
+    println!("  • Tenant: {} v{}", manifest.name, manifest.version);
+    println!("  • Entrypoint: {}", manifest.entrypoint);
+    for export in &manifest.exports {
+        println!("  • Export: {}", export);
+    }
+    println!("  • Backend: {}", manifest.backend.as_deref().unwrap_or("any"));
+    println!("  • Permissions: {}", manifest.permissions.join(", "));
+    let l = &manifest.limits;
+    println!(
+        "  • Limits: fuel={:?} timeout_ms={:?} max_memory_mb={:?} max_table_elements={:?} max_instances={:?}",
+        l.fuel, l.timeout_ms, l.max_memory_mb, l.max_table_elements, l.max_instances
+    );
+    for m in &manifest.mounts {
+        println!("  • Mount: {} → {} ({})", m.host.display(), m.guest, if m.read_only { "ro" } else { "rw" });
+    }
+    if let Some(d) = &manifest.deterministic {
+        println!(
+            "  • Deterministic: seed={} clock_start_ms={} clock_step_ns={}",
+            d.seed, d.clock_start_ms, d.clock_step_ns
+        );
+    }
+    let c = manifest.compiler.unwrap_or_default().or(policy.compiler);
+    println!(
+        "  • Compiler: {} simd={:?} relaxed_simd={:?} threads={:?} multi_memory={:?} gc={:?} max_wasm_stack_kb={:?}",
+        format!("{:?}", c.strategy.unwrap_or_default()).to_lowercase(),
+        c.simd, c.relaxed_simd, c.threads, c.multi_memory, c.gc, c.max_wasm_stack_kb
+    );
+
+    let module_path = dir.join("module.wasm");
+    if module_path.exists() {
+        let mut caps = manifest.capabilities()?;
+        restrict_capabilities(&mut caps, policy);
+        inspect_module(&module_path, &caps)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,307 @@
+use crate::{audit, escape_html, policy::Policy, scheduler, verify};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use nc_exec::{FailureClass, SandboxBackend, VerifyStage};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+
+// ===========================================================
+// 👁️ Night Core Watch — re-verify tenants as modules/ changes
+// ===========================================================
+// inotify reports writes to a tenant's module, signature, key or manifest.
+// Once the tenant has been quiet for the debounce window it is re-verified
+// (and optionally run). A failed verification raises a hash-chained audit
+// event and an alert shown on the dashboards until a later change verifies
+// again: a signature alert when the module no longer matches a valid
+// signature, a module check alert when it is signed but fails analysis or
+// the import check.
+
+/// Files whose change can alter a tenant's verification result.
+const TRACKED_FILES: &[&str] = &["module.wasm", "module.sig", "module.sha256", "pubkey.b64", "manifest.json"];
+
+const ALERTS_PATH: &str = "logs/signature_alerts.json";
+
+/// Why a changed tenant no longer verifies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    /// No matching valid signature (or key / signature files unusable).
+    #[default]
+    Signature,
+    /// Signed, but unparsable or importing what its capabilities deny.
+    ModuleCheck,
+}
+
+/// A changed tenant that no longer verifies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchAlert {
+    #[serde(default)]
+    pub kind: AlertKind,
+    pub detected_at: String,
+    pub sha256: String,
+    /// Files whose change triggered the failed check.
+    pub changed: Vec<String>,
+    pub error: String,
+}
+
+enum Change {
+    /// `file` changed in (or was moved into) `modules/<tenant>/`.
+    File { tenant: String, file: String },
+    /// A tenant directory appeared.
+    Added(String),
+    Removed(String),
+}
+
+/// 👁️ Watch `modules/` until the process is stopped.
+pub fn watch(sandbox: Arc<dyn SandboxBackend + Send + Sync>, policy: Policy, run: bool) -> Result<()> {
+    let modules_dir = PathBuf::from("modules");
+    let inotify = Inotify::init().context("initialising inotify")?;
+    let (tx, rx) = mpsc::channel();
+    let watcher = {
+        let modules_dir = modules_dir.clone();
+        thread::spawn(move || watch_modules(inotify, &modules_dir, tx))
+    };
+    let debounce = Duration::from_millis(policy.watch.debounce_ms);
+    println!("👁️  Watching {} (debounce {} ms, run on change: {})",
+        modules_dir.display(), debounce.as_millis(), if run { "yes" } else { "no" });
+
+    loop {
+        // The watcher only drops its sender when it fails.
+        let Ok(first) = rx.recv() else {
+            return match watcher.join() {
+                Ok(Err(e)) => Err(e.context("watching modules/")),
+                _ => Err(anyhow!("modules/ watcher stopped")),
+            };
+        };
+        let mut changed: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut removed = BTreeSet::new();
+        let mut pending = Some(first);
+        while let Some(change) = pending {
+            match change {
+                Change::File { tenant, file } => {
+                    removed.remove(&tenant);
+                    changed.entry(tenant).or_default().insert(file);
+                }
+                Change::Added(tenant) => {
+                    removed.remove(&tenant);
+                    changed.entry(tenant).or_default();
+                }
+                Change::Removed(tenant) => {
+                    changed.remove(&tenant);
+                    removed.insert(tenant);
+                }
+            }
+            pending = rx.recv_timeout(debounce).ok();
+        }
+        handle_changes(&modules_dir, changed, removed, &sandbox, &policy, run)?;
+    }
+}
+
+/// Forward relevant inotify events until reading them fails.
+fn watch_modules(mut inotify: Inotify, modules_dir: &Path, tx: mpsc::Sender<Change>) -> Result<()> {
+    let root = inotify.watches()
+        .add(modules_dir, WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::DELETE | WatchMask::MOVED_FROM)
+        .with_context(|| format!("watching {}", modules_dir.display()))?;
+    let mut tenants: HashMap<WatchDescriptor, String> = HashMap::new();
+    for entry in fs::read_dir(modules_dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            watch_tenant(&mut inotify, &path, &mut tenants)?;
+        }
+    }
+
+    let mut buffer = [0u8; 4096];
+    loop {
+        for event in inotify.read_events_blocking(&mut buffer)? {
+            let Some(name) = event.name.map(|n| n.to_string_lossy().into_owned()) else { continue };
+            let change = if event.wd == root {
+                if !event.mask.contains(EventMask::ISDIR) {
+                    continue;
+                }
+                if event.mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+                    watch_tenant(&mut inotify, &modules_dir.join(&name), &mut tenants)?;
+                    Change::Added(name)
+                } else {
+                    // A deleted directory drops its own watch; a moved one does not.
+                    if let Some(wd) = tenants.iter().find(|(_, t)| **t == name).map(|(wd, _)| wd.clone()) {
+                        let _ = inotify.watches().remove(wd.clone());
+                        tenants.remove(&wd);
+                    }
+                    Change::Removed(name)
+                }
+            } else if let Some(tenant) = tenants.get(&event.wd) {
+                if !TRACKED_FILES.contains(&name.as_str()) {
+                    continue;
+                }
+                Change::File { tenant: tenant.clone(), file: name }
+            } else {
+                continue;
+            };
+            if tx.send(change).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn watch_tenant(inotify: &mut Inotify, dir: &Path, tenants: &mut HashMap<WatchDescriptor, String>) -> Result<()> {
+    let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::DELETE | WatchMask::MOVED_FROM;
+    let wd = inotify.watches().add(dir, mask).with_context(|| format!("watching {}", dir.display()))?;
+    let name = dir.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    tenants.insert(wd, name);
+    Ok(())
+}
+
+/// Re-verify every changed tenant, raise or clear alerts, then run the ones
+/// that verified when `run` is set.
+fn handle_changes(
+    modules_dir: &Path,
+    changed: BTreeMap<String, BTreeSet<String>>,
+    removed: BTreeSet<String>,
+    sandbox: &Arc<dyn SandboxBackend + Send + Sync>,
+    policy: &Policy,
+    run: bool,
+) -> Result<()> {
+    let mut alerts = load_alerts();
+    let mut alerts_changed = false;
+    let mut verified = vec![];
+
+    for tenant in removed {
+        println!("🗑️  {}: removed from {}", tenant, modules_dir.display());
+        alerts_changed |= alerts.remove(&tenant).is_some();
+    }
+    for (tenant, files) in changed {
+        let dir = modules_dir.join(&tenant);
+        if !dir.join("module.wasm").exists() {
+            println!("⏳ {}: no module.wasm yet", tenant);
+            continue;
+        }
+        let files: Vec<String> = files.into_iter().collect();
+        let what = if files.is_empty() { "new tenant".to_string() } else { files.join(", ") };
+        println!("🔄 {}: changed ({}), re-verifying", tenant, what);
+
+        match verify::verify_tenant(&dir, sandbox.as_ref(), policy) {
+            Ok(sha) => {
+                println!("✅ {}: verified (SHA-256 {})", tenant, sha);
+                record_audit("tenant.reverified", serde_json::json!({
+                    "tenant": tenant, "sha256": sha, "changed": files,
+                }));
+                if alerts.remove(&tenant).is_some() {
+                    println!("🟢 {}: alert cleared", tenant);
+                    alerts_changed = true;
+                }
+                verified.push(dir);
+            }
+            Err(e) if verify::failure_class(&e) == FailureClass::Verification => {
+                let sha = fs::read(dir.join("module.wasm"))
+                    .map(|bytes| format!("{:X}", Sha256::digest(&bytes)))
+                    .unwrap_or_default();
+                let kind = match e.downcast_ref::<VerifyStage>() {
+                    Some(VerifyStage::Imports) => AlertKind::ModuleCheck,
+                    _ => AlertKind::Signature,
+                };
+                let event = match kind {
+                    AlertKind::Signature => {
+                        eprintln!("🚨 {}: changed module has no matching valid signature — {:#}", tenant, e);
+                        "tenant.verification_failed"
+                    }
+                    AlertKind::ModuleCheck => {
+                        eprintln!("🚨 {}: changed module is signed but fails analysis / import checks — {:#}", tenant, e);
+                        "tenant.module_check_failed"
+                    }
+                };
+                record_audit(event, serde_json::json!({
+                    "tenant": tenant, "sha256": sha, "changed": files, "error": format!("{:#}", e),
+                }));
+                alerts.insert(tenant, WatchAlert {
+                    kind,
+                    detected_at: Utc::now().to_rfc3339(),
+                    sha256: sha,
+                    changed: files,
+                    error: format!("{:#}", e),
+                });
+                alerts_changed = true;
+            }
+            Err(e) => eprintln!("⚠️  {}: cannot re-verify: {:#}", tenant, e),
+        }
+    }
+
+    if alerts_changed {
+        save_alerts(&alerts)?;
+        crate::refresh_live_dashboard()?;
+        println!("🖥️  Dashboard updated ({} open alert(s))", alerts.len());
+    }
+    if run && !verified.is_empty() {
+        let job = crate::tenant_job(sandbox, policy, false);
+        let parallel = policy.orchestration.parallel_limit;
+        scheduler::run_queue(verified, parallel, crate::tenant_rules(policy), job, |done| {
+            let last = done.attempts.last().unwrap();
+            let name = done.job.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            println!("{:<20} {:<40} ⏱️  {:.2}s ({} attempt(s))",
+                name, crate::attempt_status(last), last.elapsed.as_secs_f64(), done.attempts.len());
+        });
+    }
+    Ok(())
+}
+
+fn record_audit(event: &str, details: serde_json::Value) {
+    if let Err(e) = audit::append(event, details) {
+        eprintln!("⚠️  Audit event {} not recorded: {:#}", event, e);
+    }
+}
+
+/// Open alerts by tenant.
+pub fn load_alerts() -> BTreeMap<String, WatchAlert> {
+    fs::read_to_string(ALERTS_PATH)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_alerts(alerts: &BTreeMap<String, WatchAlert>) -> Result<()> {
+    fs::create_dir_all("logs")?;
+    fs::write(ALERTS_PATH, serde_json::to_string_pretty(alerts)?)
+        .with_context(|| format!("writing {}", ALERTS_PATH))
+}
+
+/// Dashboard banner listing open alerts, one box per kind; empty when there
+/// are none.
+pub fn alert_banner() -> String {
+    let alerts = load_alerts();
+    let mut html = String::new();
+    for (kind, heading) in [
+        (AlertKind::Signature, "<b>⚠️ Signature alerts</b> — modules changed without a matching valid signature:"),
+        (AlertKind::ModuleCheck, "<b>⚠️ Module check alerts</b> — signed modules that fail analysis or the import check:"),
+    ] {
+        let open: Vec<_> = alerts.iter().filter(|(_, a)| a.kind == kind).collect();
+        if open.is_empty() {
+            continue;
+        }
+        html.push_str(
+            "<div style='border:1px solid #6b1f1f;background:#241010;color:#ff6b6b;\
+             border-radius:8px;padding:10px 14px;margin:12px 0;'>",
+        );
+        html.push_str(heading);
+        html.push_str("<ul>");
+        // Tenant and file names come from the filesystem and errors may quote
+        // them, so none of it is trusted as markup.
+        for (tenant, alert) in open {
+            html.push_str(&format!(
+                "<li><b>{}</b> ({}) at {}: {}</li>",
+                escape_html(tenant), escape_html(&alert.changed.join(", ")),
+                escape_html(&alert.detected_at), escape_html(&alert.error)
+            ));
+        }
+        html.push_str("</ul></div>");
+    }
+    html
+}